@@ -0,0 +1,427 @@
+//! API keys for the RPC server, loaded from a JSON file like
+//! `{ "keys": [{ "id": "gateway", "secret": "..", "scope": "trade" }] }`.
+//!
+//! A request authenticates with `Authorization: Bearer <secret>`, or signs
+//! its body so the secret never goes over the wire:
+//! `X-Link-Key-Id: <id>`, `X-Link-Timestamp: <unix seconds>` and
+//! `X-Link-Signature: <hex HMAC-SHA256 of "<timestamp>.<body>">`.
+//! Each signature is accepted once, a client sending the same body twice
+//! must sign it again with a later timestamp.
+//!
+//! To rotate a key, add its replacement under a new id, move clients over,
+//! then remove the old one or let it expire. The file is read again on
+//! `SIGHUP`.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac as _};
+use hyper::HeaderMap;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::{Zeroize as _, Zeroizing};
+
+use crate::error::Error;
+use crate::message::{ErrorCode, RpcError, RpcParams};
+
+pub const KEY_ID_HEADER: &str = "x-link-key-id";
+pub const TIMESTAMP_HEADER: &str = "x-link-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-link-signature";
+
+/// Seconds a signed request's timestamp may be off from the server's clock
+const MAX_CLOCK_SKEW: u64 = 300;
+const MIN_SECRET_LENGTH: usize = 32;
+
+/// What a key may call, each scope includes the ones before it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum Scope {
+    /// `getAccount` and `quote`
+    Read,
+    /// `buy` and `sell` too
+    Trade,
+    /// Everything, including launching tokens with `create`
+    Admin,
+}
+
+impl Scope {
+    /// The scope a request needs.
+    pub fn required(params: &RpcParams) -> Self {
+        match params {
+            RpcParams::GetAccount(_) | RpcParams::Quote(_) => Self::Read,
+            RpcParams::Buy(_) | RpcParams::Sell(_) => Self::Trade,
+            RpcParams::Create(_) => Self::Admin,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    pub id: String,
+    secret: String,
+    pub scope: Scope,
+    /// Unix time from which the key is refused
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+impl std::fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("id", &self.id)
+            .field("scope", &self.scope)
+            .field("expires_at", &self.expires_at)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for ApiKey {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl ApiKey {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    fn mac(&self, timestamp: &str, body: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC takes keys of any length");
+        mac.update(timestamp.as_bytes());
+        mac.update(b".");
+        mac.update(body);
+        mac
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct KeyFile {
+    keys: Vec<ApiKey>,
+}
+
+/// The keys allowed to call the server.
+#[derive(Debug)]
+pub struct ApiKeys {
+    path: PathBuf,
+    keys: RwLock<Arc<Vec<ApiKey>>>,
+    /// Signatures already accepted, by key id, until their timestamp is
+    /// too old to be accepted anyway
+    seen: Mutex<HashMap<(String, Vec<u8>), u64>>,
+}
+
+impl ApiKeys {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let keys = Self::read(&path)?;
+        Ok(Self {
+            path,
+            keys: RwLock::new(Arc::new(keys)),
+            seen: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Read the file again, keeping the current keys if it's invalid.
+    /// Returns the number of keys loaded.
+    pub fn reload(&self) -> Result<usize, Error> {
+        let keys = Self::read(&self.path)?;
+        let count = keys.len();
+        *self.keys.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(keys);
+        Ok(count)
+    }
+
+    fn read(path: &Path) -> Result<Vec<ApiKey>, Error> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            Error::Generic(format!("invalid api keys {}: {}", path.display(), e))
+        };
+        let data = Zeroizing::new(std::fs::read_to_string(path).map_err(|e| invalid(&e))?);
+        let file: KeyFile = serde_json::from_str(&data).map_err(|e| invalid(&e))?;
+
+        let mut ids = HashSet::new();
+        for key in &file.keys {
+            if !ids.insert(key.id.as_str()) {
+                return Err(invalid(&format!("duplicate key id {:?}", key.id)));
+            }
+            if key.secret.len() < MIN_SECRET_LENGTH {
+                return Err(invalid(&format!(
+                    "the secret of {:?} is shorter than {} bytes",
+                    key.id, MIN_SECRET_LENGTH
+                )));
+            }
+        }
+        Ok(file.keys)
+    }
+
+    /// Reload the keys whenever the process gets `SIGHUP`.
+    pub fn reload_on_hangup(self: Arc<Self>) -> Result<(), Error> {
+        let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+            .map_err(|e| Error::Generic(format!("error handling SIGHUP: {}", e)))?;
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                match self.reload() {
+                    Ok(count) => tracing::info!(count, "reloaded api keys"),
+                    Err(e) => tracing::error!(error = %e, "keeping the current api keys"),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn keys(&self) -> Arc<Vec<ApiKey>> {
+        self.keys.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// The scope of the key a request was made with.
+    pub fn authenticate(&self, headers: &HeaderMap, body: &[u8]) -> Result<Scope, RpcError> {
+        self.authenticate_at(headers, body, now())
+    }
+
+    fn authenticate_at(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        now: u64,
+    ) -> Result<Scope, RpcError> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let keys = self.keys();
+
+        let key = if let Some(authorization) = header(hyper::header::AUTHORIZATION.as_str()) {
+            let secret = authorization
+                .strip_prefix("Bearer ")
+                .ok_or_else(|| unauthorized("unsupported authorization scheme"))?;
+            keys.iter()
+                .find(|key| constant_time_eq(key.secret.as_bytes(), secret.as_bytes()))
+                .ok_or_else(|| unauthorized("invalid api key"))?
+        } else if let Some(id) = header(KEY_ID_HEADER) {
+            let key = keys
+                .iter()
+                .find(|key| key.id == id)
+                .ok_or_else(|| unauthorized("invalid api key"))?;
+            let timestamp = header(TIMESTAMP_HEADER)
+                .ok_or_else(|| unauthorized(&format!("missing {} header", TIMESTAMP_HEADER)))?;
+            let signature = header(SIGNATURE_HEADER)
+                .and_then(|signature| hex::decode(signature).ok())
+                .ok_or_else(|| {
+                    unauthorized(&format!("missing or invalid {} header", SIGNATURE_HEADER))
+                })?;
+
+            let time: u64 = timestamp
+                .parse()
+                .map_err(|_| unauthorized("invalid timestamp"))?;
+            if time.abs_diff(now) > MAX_CLOCK_SKEW {
+                return Err(unauthorized("timestamp too far from the server's clock"));
+            }
+            key.mac(timestamp, body)
+                .verify_slice(&signature)
+                .map_err(|_| unauthorized("invalid signature"))?;
+            self.check_replay(&key.id, signature, time + MAX_CLOCK_SKEW, now)?;
+            key
+        } else {
+            return Err(unauthorized("missing api key"));
+        };
+
+        if key.is_expired(now) {
+            return Err(unauthorized("api key expired"));
+        }
+        Ok(key.scope)
+    }
+
+    /// Refuse a signature seen before, and forget the ones that expired.
+    fn check_replay(
+        &self,
+        id: &str,
+        signature: Vec<u8>,
+        expires_at: u64,
+        now: u64,
+    ) -> Result<(), RpcError> {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.retain(|_, expires_at| *expires_at >= now);
+        // The same signature always carries the same timestamp
+        if seen
+            .insert((id.to_string(), signature), expires_at)
+            .is_some()
+        {
+            return Err(unauthorized("signature already used"));
+        }
+        Ok(())
+    }
+}
+
+fn unauthorized(message: &str) -> RpcError {
+    RpcError::new(ErrorCode::Unauthorized, message)
+}
+
+/// Compare secrets without leaking how much of them matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const NOW: u64 = 1_760_000_000;
+    const SECRET: &str = "0123456789abcdef0123456789abcdef";
+    const BODY: &[u8] = br#"{"jsonrpc":"2.0","id":1,"method":"quote"}"#;
+
+    fn keys(file: serde_json::Value) -> (ApiKeys, PathBuf) {
+        let path = std::env::temp_dir().join(format!(
+            "x-link-api-keys-{}-{:?}.json",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        std::fs::write(&path, file.to_string()).unwrap();
+        (ApiKeys::load(&path).unwrap(), path)
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    fn signed(id: &'static str, secret: &str, timestamp: u64, body: &[u8]) -> HeaderMap {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("{}.", timestamp).as_bytes());
+        mac.update(body);
+        let signature = hex::encode(mac.finalize().into_bytes());
+        headers(&[
+            (KEY_ID_HEADER, id),
+            (TIMESTAMP_HEADER, &timestamp.to_string()),
+            (SIGNATURE_HEADER, &signature),
+        ])
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let (keys, path) = keys(json!({
+            "keys": [
+                { "id": "reader", "secret": SECRET, "scope": "read" },
+                { "id": "old", "secret": "x".repeat(32), "scope": "trade", "expiresAt": NOW },
+            ]
+        }));
+        let auth = |headers: HeaderMap, body: &[u8]| keys.authenticate_at(&headers, body, NOW);
+        let bearer = |secret: &str| headers(&[("authorization", &format!("Bearer {}", secret))]);
+
+        assert_eq!(auth(bearer(SECRET), BODY).unwrap(), Scope::Read);
+        assert_eq!(
+            auth(signed("reader", SECRET, NOW - 10, BODY), BODY).unwrap(),
+            Scope::Read
+        );
+
+        for headers in [
+            HeaderMap::new(),
+            bearer("wrong"),
+            headers(&[("authorization", &format!("Basic {}", SECRET))]),
+            // Expired
+            bearer(&"x".repeat(32)),
+            signed("reader", "wrong", NOW, BODY),
+            signed("unknown", SECRET, NOW, BODY),
+            signed("reader", SECRET, NOW - MAX_CLOCK_SKEW - 1, BODY),
+        ] {
+            let error = auth(headers.clone(), BODY).unwrap_err();
+            assert_eq!(error.code, ErrorCode::Unauthorized, "{:?}", headers);
+        }
+        // Signed for another body
+        assert!(auth(signed("reader", SECRET, NOW, b"{}"), BODY).is_err());
+
+        // Replayed
+        let error = auth(signed("reader", SECRET, NOW - 10, BODY), BODY).unwrap_err();
+        assert_eq!(error.code, ErrorCode::Unauthorized);
+        assert_eq!(error.message, "signature already used");
+        assert_eq!(
+            auth(signed("reader", SECRET, NOW - 9, BODY), BODY).unwrap(),
+            Scope::Read
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_replay() {
+        let (keys, path) = keys(json!({
+            "keys": [{ "id": "a", "secret": SECRET, "scope": "trade" }]
+        }));
+        let headers = signed("a", SECRET, NOW, BODY);
+        assert!(keys.authenticate_at(&headers, BODY, NOW).is_ok());
+        assert!(keys
+            .authenticate_at(&headers, BODY, NOW + MAX_CLOCK_SKEW)
+            .is_err());
+
+        // Forgotten once the timestamp is refused anyway
+        let later = NOW + MAX_CLOCK_SKEW + 1;
+        assert!(keys.authenticate_at(&headers, BODY, later).is_err());
+        assert!(keys
+            .authenticate_at(&signed("a", SECRET, later, BODY), BODY, later)
+            .is_ok());
+        assert_eq!(keys.seen.lock().unwrap().len(), 1);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_reload() {
+        let (keys, path) = keys(json!({
+            "keys": [{ "id": "a", "secret": SECRET, "scope": "admin" }]
+        }));
+        let bearer = headers(&[("authorization", &format!("Bearer {}", SECRET))]);
+        assert_eq!(
+            keys.authenticate_at(&bearer, BODY, NOW).unwrap(),
+            Scope::Admin
+        );
+
+        // Rotated to a new key
+        let secret = "fedcba9876543210fedcba9876543210";
+        std::fs::write(
+            &path,
+            json!({ "keys": [{ "id": "b", "secret": secret, "scope": "read" }] }).to_string(),
+        )
+        .unwrap();
+        assert_eq!(keys.reload().unwrap(), 1);
+        assert!(keys.authenticate_at(&bearer, BODY, NOW).is_err());
+
+        // An invalid file keeps the current keys
+        std::fs::write(
+            &path,
+            json!({ "keys": [{ "id": "c", "secret": "short", "scope": "read" }] }).to_string(),
+        )
+        .unwrap();
+        assert!(keys.reload().is_err());
+        let bearer = headers(&[("authorization", &format!("Bearer {}", secret))]);
+        assert_eq!(
+            keys.authenticate_at(&bearer, BODY, NOW).unwrap(),
+            Scope::Read
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_scope() {
+        assert!(Scope::Admin > Scope::Trade && Scope::Trade > Scope::Read);
+        let request: crate::message::RpcRequest = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getAccount",
+            "params": { "twitterId": "123456" }
+        }))
+        .unwrap();
+        assert_eq!(Scope::required(&request.params), Scope::Read);
+    }
+}
@@ -0,0 +1,180 @@
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use lru::LruCache;
+use serde::Serialize;
+
+use crate::wallet::Wallet;
+
+/// Twitter ID and wallet index of a wallet.
+///
+/// The scheme isn't part of the key: the scheme table is loaded with the seed
+/// and never changes for the life of the process, so an ID is always served
+/// under the same scheme. A migration written while the client runs only
+/// takes effect on restart, with an empty cache.
+pub type CacheKey = (u64, u32);
+
+/// Recently used wallets, so hot twitter IDs skip the derivation.
+///
+/// Bounded by size and age. Local keypairs live in locked memory that is
+/// wiped once the last request holding an evicted wallet is done with it.
+pub struct WalletCache {
+    /// `None` when caching is disabled
+    entries: Option<Mutex<LruCache<CacheKey, Entry>>>,
+    ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+struct Entry {
+    wallet: Arc<Wallet>,
+    inserted: Instant,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Wallets dropped for space or age
+    pub evictions: u64,
+    pub len: usize,
+}
+
+impl WalletCache {
+    /// Keep up to `capacity` wallets for at most `ttl`, a capacity of 0
+    /// disables the cache.
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: NonZeroUsize::new(capacity)
+                .map(|capacity| Mutex::new(LruCache::new(capacity))),
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    pub fn get(&self, key: CacheKey) -> Option<Arc<Wallet>> {
+        let wallet = self.entries.as_ref().and_then(|entries| {
+            let mut entries = entries.lock().expect("wallet cache poisoned");
+            match entries.get(&key) {
+                Some(entry) if entry.inserted.elapsed() <= self.ttl => Some(entry.wallet.clone()),
+                Some(_) => {
+                    entries.pop(&key);
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                    None
+                }
+                None => None,
+            }
+        });
+
+        let counter = if wallet.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        tracing::trace!(
+            twitter_id = key.0,
+            wallet_index = key.1,
+            hit = wallet.is_some(),
+            "wallet cache lookup"
+        );
+        wallet
+    }
+
+    pub fn insert(&self, key: CacheKey, wallet: Arc<Wallet>) {
+        let Some(entries) = &self.entries else {
+            return;
+        };
+        let entry = Entry {
+            wallet,
+            inserted: Instant::now(),
+        };
+        let evicted = entries
+            .lock()
+            .expect("wallet cache poisoned")
+            .push(key, entry);
+        // `push` also hands back the entry it replaced under the same key
+        if matches!(evicted, Some((evicted_key, _)) if evicted_key != key) {
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            len: self.entries.as_ref().map_or(0, |entries| {
+                entries.lock().expect("wallet cache poisoned").len()
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signature::Keypair;
+    use x_link_types::{account::Account, social::SocialId, twitter::TwitterId};
+
+    use super::*;
+
+    fn wallet(twitter_id: u64) -> Arc<Wallet> {
+        Arc::new(Wallet::Local(Account::new(
+            SocialId::Twitter(TwitterId::new(twitter_id).unwrap()),
+            Keypair::new(),
+            1,
+        )))
+    }
+
+    #[test]
+    fn test_lru() {
+        let cache = WalletCache::new(2, Duration::from_secs(60));
+        assert!(cache.get((1, 0)).is_none());
+
+        let first = wallet(1);
+        cache.insert((1, 0), first.clone());
+        cache.insert((2, 0), wallet(2));
+        assert!(Arc::ptr_eq(&cache.get((1, 0)).unwrap(), &first));
+        // Another index is another wallet
+        assert!(cache.get((1, 1)).is_none());
+
+        // 2 is the least recently used
+        cache.insert((3, 0), wallet(3));
+        assert!(cache.get((2, 0)).is_none());
+        assert!(cache.get((1, 0)).is_some());
+
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 3,
+                evictions: 1,
+                len: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_ttl() {
+        let cache = WalletCache::new(2, Duration::ZERO);
+        cache.insert((1, 0), wallet(1));
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(cache.get((1, 0)).is_none());
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!(cache.stats().len, 0);
+    }
+
+    #[test]
+    fn test_disabled() {
+        let cache = WalletCache::new(0, Duration::from_secs(60));
+        cache.insert((1, 0), wallet(1));
+        assert!(cache.get((1, 0)).is_none());
+        assert_eq!(cache.stats().len, 0);
+    }
+}
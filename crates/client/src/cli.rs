@@ -1,16 +1,87 @@
-use crate::{client::RpcClient, error::Error};
+use std::sync::Arc;
+use std::time::Duration;
+
+use x_link_signer::client::SignerClient;
+use x_link_wallet::{args::SecretArgs, exports::Exports};
+
+use crate::{
+    auth::ApiKeys,
+    cache::WalletCache,
+    client::{RpcClient, DEFAULT_MAX_BATCH_SIZE},
+    error::Error,
+    wallet::WalletSource,
+};
 
 #[derive(clap::Parser)]
 pub struct Args {
-    #[clap(long)]
-    secret_file: String,
+    #[clap(flatten)]
+    secret: SecretArgs,
+
+    /// Socket of an `x-link-signer` holding the seed, instead of loading it here
+    #[clap(long, conflicts_with_all = ["secret_file", "share_file"])]
+    signer_socket: Option<String>,
 
     #[clap(long, default_value = "1337")]
     port: u16,
+
+    /// Number of derived wallets kept in memory, 0 disables the cache
+    #[clap(long, default_value_t = 1024)]
+    cache_size: usize,
+
+    /// Seconds a derived wallet stays cached
+    #[clap(long, default_value_t = 300)]
+    cache_ttl: u64,
+
+    /// Most requests accepted in one JSON-RPC batch
+    #[clap(long, default_value_t = DEFAULT_MAX_BATCH_SIZE)]
+    max_batch_size: usize,
+
+    /// JSON file of the API keys requests must carry, read again on SIGHUP
+    #[clap(long, required_unless_present = "no_auth")]
+    api_keys: Option<String>,
+
+    /// Let anyone who can reach the port call every method
+    #[clap(long, conflicts_with = "api_keys")]
+    no_auth: bool,
 }
 
 impl Args {
     pub async fn run(&self) -> Result<(), Error> {
-        RpcClient::start(&self.secret_file, self.port).await
+        let source = self
+            .secret
+            .source()
+            .map_err(|e| Error::Generic(e.to_string()))?;
+        let (wallets, exports) = match (&self.signer_socket, source) {
+            // The signer warns about exported keys itself
+            (Some(socket), _) => (
+                WalletSource::Signer(Arc::new(SignerClient::new(socket))),
+                Exports::default(),
+            ),
+            (None, Some(source)) => {
+                let keygen = source
+                    .load(&self.secret.passphrase.source())
+                    .map_err(|e| Error::Generic(format!("error loading keygen: {}", e)))?;
+                let exports = Exports::load(source.files())
+                    .map_err(|e| Error::Generic(format!("error loading export log: {}", e)))?;
+                (WalletSource::Local(Arc::new(keygen)), exports)
+            }
+            (None, None) => {
+                return Err(Error::Generic(
+                    "one of --secret-file, --share-file or --signer-socket is required".to_string(),
+                ))
+            }
+        };
+        let cache = WalletCache::new(self.cache_size, Duration::from_secs(self.cache_ttl));
+        let mut client =
+            RpcClient::new(wallets, exports, cache).with_max_batch_size(self.max_batch_size);
+        match &self.api_keys {
+            Some(path) => {
+                let api_keys = Arc::new(ApiKeys::load(path)?);
+                api_keys.clone().reload_on_hangup()?;
+                client = client.with_api_keys(api_keys);
+            }
+            None => tracing::warn!("--no-auth: anyone reaching port {} can trade", self.port),
+        }
+        client.start(self.port).await
     }
 }
@@ -1,43 +1,67 @@
+use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
 use http_body_util::{BodyExt, Full};
-use hyper::body::{Buf, Bytes};
+use hyper::body::Bytes;
 use hyper::Response;
 use solana_sdk::signature::Signature;
-use x_link_types::account::Account;
-use x_link_wallet::keygen::{KeyGen, KeyGenerator as _};
 
+use x_link_solana::backend::trench::Launch;
+use x_link_types::{chain::Chain, twitter::TwitterId};
+use x_link_wallet::exports::Exports;
+
+use crate::auth::{ApiKeys, Scope};
+use crate::cache::WalletCache;
 use crate::error::Error;
+use crate::wallet::{Wallet, WalletSource};
 
 use crate::message::{
-    BuyParams, CreateParams, GetAccountParams, QuoteParams, RpcParams, RpcRequest, RpcResponse,
-    SellParams,
+    BuyParams, CreateParams, ErrorCode, GetAccountParams, QuoteParams, RpcError, RpcId, RpcParams,
+    RpcReply, RpcRequest, RpcResponse, SellParams,
 };
 
+/// Requests accepted in one JSON-RPC batch unless configured otherwise.
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 100;
+
 #[derive(Clone)]
 pub struct RpcClient {
-    keygen: Arc<KeyGen>,
+    wallets: WalletSource,
+    /// Keys exported with `x-link-wallet export`, warned about on use
+    exports: Arc<Exports>,
+    cache: Arc<WalletCache>,
     backend: Arc<x_link_solana::client::Client>,
+    /// Larger batches are rejected whole
+    max_batch_size: usize,
+    /// Keys requests must carry, none when anyone may call everything
+    api_keys: Option<Arc<ApiKeys>>,
 }
 
 impl RpcClient {
-    pub fn new(keygen: Arc<KeyGen>) -> Self {
+    pub fn new(wallets: WalletSource, exports: Exports, cache: WalletCache) -> Self {
         Self {
-            keygen,
+            wallets,
+            exports: Arc::new(exports),
+            cache: Arc::new(cache),
             backend: Arc::new(x_link_solana::client::Client::default()),
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            api_keys: None,
         }
     }
 
-    pub async fn start(secret_file: &str, port: u16) -> Result<(), Error> {
-        let passphrase = rpassword::prompt_password("Enter passphrase: ")
-            .map_err(|e| Error::Generic(format!("error reading passphrase: {}", e)))?;
-        let keygen = KeyGen::load(secret_file, &passphrase)
-            .map_err(|e| Error::Generic(format!("error loading keygen: {}", e)))?;
-        let client = Self::new(Arc::new(keygen));
-        client
-            .run(port)
+    pub fn with_api_keys(mut self, api_keys: Arc<ApiKeys>) -> Self {
+        self.api_keys = Some(api_keys);
+        self
+    }
+
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size;
+        self
+    }
+
+    pub async fn start(self, port: u16) -> Result<(), Error> {
+        self.run(port)
             .await
             .map_err(|e| Error::Generic(format!("error running rpc server: {}", e)))
     }
@@ -69,91 +93,276 @@ impl RpcClient {
         )
     }
 
-    fn get_account_by_id(&self, twitter_id: u64) -> Result<Account, Error> {
-        Ok(Account {
-            twitter_id,
-            wallet: self
-                .keygen
-                .generate_key(twitter_id)
-                .map_err(|e| Error::Generic(format!("error generating key: {}", e)))?,
-        })
+    async fn get_account_by_id(
+        &self,
+        twitter_id: TwitterId,
+        wallet_index: Option<u32>,
+    ) -> Result<Arc<Wallet>, Error> {
+        let wallet_index = wallet_index.unwrap_or_default();
+        // Only main wallets can be exported
+        if wallet_index == 0 {
+            self.exports.warn_if_exported(twitter_id.get());
+        }
+        let key = (twitter_id.get(), wallet_index);
+        if let Some(wallet) = self.cache.get(key) {
+            return Ok(wallet);
+        }
+
+        let wallet = Arc::new(self.wallets.wallet(twitter_id, wallet_index).await?);
+        self.cache.insert(key, wallet.clone());
+        tracing::debug!(stats = ?self.cache.stats(), "wallet cache miss");
+        Ok(wallet)
+    }
+
+    /// Refuse to trade from a wallet whose key was exported, unless the
+    /// request allows it.
+    fn check_exported(&self, account: &Wallet, allow_exported: bool) -> Result<(), Error> {
+        match account.exported_at(&self.exports) {
+            Some(exported_at) if !allow_exported => Err(Error::Exported(exported_at)),
+            _ => Ok(()),
+        }
     }
 
     async fn handle_buy_inner(
         &self,
-        account: Account,
+        account: Arc<Wallet>,
         params: BuyParams,
     ) -> Result<Signature, Error> {
+        self.check_exported(&account, params.allow_exported)?;
         Ok(self
             .backend
-            .buy(&account, &params.token_id, params.amount)
+            .buy(&*account, &params.token_id, params.amount)
             .await?)
     }
 
     async fn handle_sell_inner(
         &self,
-        account: Account,
+        account: Arc<Wallet>,
         params: SellParams,
     ) -> Result<Signature, Error> {
+        self.check_exported(&account, params.allow_exported)?;
         Ok(self
             .backend
-            .sell(&account, &params.token_id, params.amount)
+            .sell(&*account, &params.token_id, params.amount)
             .await?)
     }
 
-    async fn handle_buy(&self, id: u64, params: BuyParams) -> RpcResponse {
-        match self.get_account_by_id(params.twitter_id) {
+    async fn handle_buy(&self, id: RpcId, params: BuyParams) -> RpcResponse {
+        match self
+            .get_account_by_id(params.twitter_id, params.wallet_index)
+            .await
+        {
             Ok(account) => match self.handle_buy_inner(account, params).await {
                 Ok(signature) => RpcResponse::ok(id).with_signature(signature),
-                Err(e) => RpcResponse::error(id, &e.to_string()),
+                Err(e) => RpcResponse::error(id, &e),
             },
-            Err(e) => RpcResponse::error(id, &e.to_string()),
+            Err(e) => RpcResponse::error(id, &e),
         }
     }
 
-    async fn handle_sell(&self, id: u64, params: SellParams) -> RpcResponse {
-        match self.get_account_by_id(params.twitter_id) {
+    async fn handle_sell(&self, id: RpcId, params: SellParams) -> RpcResponse {
+        match self
+            .get_account_by_id(params.twitter_id, params.wallet_index)
+            .await
+        {
             Ok(account) => match self.handle_sell_inner(account, params).await {
                 Ok(signature) => RpcResponse::ok(id).with_signature(signature),
-                Err(e) => RpcResponse::error(id, &e.to_string()),
+                Err(e) => RpcResponse::error(id, &e),
             },
-            Err(e) => RpcResponse::error(id, &e.to_string()),
+            Err(e) => RpcResponse::error(id, &e),
         }
     }
 
-    async fn handle_quote(&self, id: u64, params: QuoteParams) -> RpcResponse {
+    async fn handle_quote(&self, id: RpcId, params: QuoteParams) -> RpcResponse {
         match self
             .backend
             .quote(params.input_mint, params.output_mint, params.amount)
             .await
         {
             Ok(quote) => RpcResponse::ok(id).with_quote(quote),
-            Err(e) => RpcResponse::error(id, &e.to_string()),
+            Err(e) => RpcResponse::error(id, &e),
         }
     }
 
-    fn handle_create(&self, id: u64, params: CreateParams) -> RpcResponse {
-        todo!()
+    async fn handle_create_inner(
+        &self,
+        account: Arc<Wallet>,
+        params: CreateParams,
+    ) -> Result<Launch, Error> {
+        self.check_exported(&account, params.allow_exported)?;
+        Ok(self
+            .backend
+            .create(&*account, &params.token.into(), params.amount)
+            .await?)
+    }
+
+    async fn handle_create(&self, id: RpcId, params: CreateParams) -> RpcResponse {
+        match self
+            .get_account_by_id(params.twitter_id, params.wallet_index)
+            .await
+        {
+            Ok(account) => match self.handle_create_inner(account, params).await {
+                Ok(launch) => RpcResponse::ok(id).with_launch(launch),
+                Err(e) => RpcResponse::error(id, &e),
+            },
+            Err(e) => RpcResponse::error(id, &e),
+        }
     }
 
-    fn handle_get_account(&self, id: u64, params: GetAccountParams) -> RpcResponse {
-        match self.get_account_by_id(params.twitter_id) {
-            Ok(account) => RpcResponse::ok(id).with_account(account),
-            Err(e) => RpcResponse::error(id, &e.to_string()),
+    async fn handle_get_account(&self, id: RpcId, params: GetAccountParams) -> RpcResponse {
+        match params.chain.unwrap_or_default() {
+            Chain::Solana => match self
+                .get_account_by_id(params.twitter_id, params.wallet_index)
+                .await
+            {
+                Ok(account) => {
+                    let info = account.info();
+                    let info = match account.exported_at(&self.exports) {
+                        Some(exported_at) => info.with_exported_at(exported_at),
+                        None => info,
+                    };
+                    RpcResponse::ok(id).with_account(info)
+                }
+                Err(e) => RpcResponse::error(id, &e),
+            },
+            Chain::Evm => match self
+                .wallets
+                .evm_account(params.twitter_id, params.wallet_index.unwrap_or_default())
+                .await
+            {
+                Ok(account) => RpcResponse::ok(id).with_account(account),
+                Err(e) => RpcResponse::error(id, &e),
+            },
         }
     }
 
     #[tracing::instrument(skip(self))]
     async fn handle(&self, req: RpcRequest) -> RpcResponse {
         tracing::debug!("handling request");
+        let id = req.id.unwrap_or_default();
         match req.params {
-            RpcParams::Buy(params) => self.handle_buy(req.id, params).await,
-            RpcParams::Sell(params) => self.handle_sell(req.id, params).await,
-            RpcParams::Create(params) => self.handle_create(req.id, params),
-            RpcParams::GetAccount(params) => self.handle_get_account(req.id, params),
-            RpcParams::Quote(params) => self.handle_quote(req.id, params).await,
+            RpcParams::Buy(params) => self.handle_buy(id, params).await,
+            RpcParams::Sell(params) => self.handle_sell(id, params).await,
+            RpcParams::Create(params) => self.handle_create(id, params).await,
+            RpcParams::GetAccount(params) => self.handle_get_account(id, params).await,
+            RpcParams::Quote(params) => self.handle_quote(id, params).await,
         }
     }
+
+    /// Scope of the key a request was made with, everything when no keys
+    /// are required.
+    fn authenticate(&self, headers: &hyper::HeaderMap, body: &[u8]) -> Result<Scope, RpcError> {
+        match &self.api_keys {
+            Some(api_keys) => api_keys.authenticate(headers, body).inspect_err(|e| {
+                tracing::warn!(error = %e.message, "unauthenticated request");
+            }),
+            None => Ok(Scope::Admin),
+        }
+    }
+
+    /// Handle one request, `None` for a notification.
+    async fn handle_value(&self, value: serde_json::Value, scope: Scope) -> Option<RpcResponse> {
+        let id = RpcId::from_request(&value);
+        match RpcRequest::from_value(value) {
+            Ok(req) if Scope::required(&req.params) > scope => {
+                let error = RpcError::new(
+                    ErrorCode::Forbidden,
+                    format!("{} is not allowed for this api key", req.method),
+                );
+                req.id.map(|id| RpcResponse::error(id, error))
+            }
+            Ok(req) => {
+                let notification = req.id.is_none();
+                let response = self.handle(req).await;
+                (!notification).then_some(response)
+            }
+            // Notifications go unanswered even when they fail, unless they
+            // are too malformed to tell they are one
+            Err(e)
+                if id.is_none()
+                    && matches!(e.code, ErrorCode::MethodNotFound | ErrorCode::InvalidParams) =>
+            {
+                None
+            }
+            Err(e) => Some(RpcResponse::error(id.unwrap_or_default(), e)),
+        }
+    }
+
+    /// Handle a request body, either a single request or a batch of them.
+    async fn handle_body(&self, body: serde_json::Value, scope: Scope) -> RpcReply {
+        match body {
+            serde_json::Value::Array(batch) if batch.is_empty() => {
+                RpcReply::Single(RpcResponse::error(
+                    RpcId::Null,
+                    RpcError::new(ErrorCode::InvalidRequest, "empty batch"),
+                ))
+            }
+            serde_json::Value::Array(batch) if batch.len() > self.max_batch_size => {
+                RpcReply::Single(RpcResponse::error(
+                    RpcId::Null,
+                    RpcError::new(
+                        ErrorCode::InvalidRequest,
+                        format!(
+                            "batch of {} requests exceeds the limit of {}",
+                            batch.len(),
+                            self.max_batch_size
+                        ),
+                    )
+                    .with_data(serde_json::json!({ "maxBatchSize": self.max_batch_size })),
+                ))
+            }
+            serde_json::Value::Array(batch) => {
+                let responses = self.handle_batch(batch, scope).await;
+                if responses.is_empty() {
+                    RpcReply::Empty
+                } else {
+                    RpcReply::Batch(responses)
+                }
+            }
+            request => self
+                .handle_value(request, scope)
+                .await
+                .map_or(RpcReply::Empty, RpcReply::Single),
+        }
+    }
+
+    /// Run every request of a batch concurrently, answering in request order.
+    async fn handle_batch(&self, batch: Vec<serde_json::Value>, scope: Scope) -> Vec<RpcResponse> {
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut ids = HashMap::new();
+        for (index, request) in batch.into_iter().enumerate() {
+            let id = RpcId::from_request(&request);
+            let handler = self.clone();
+            let task =
+                tasks.spawn(async move { (index, handler.handle_value(request, scope).await) });
+            ids.insert(task.id(), (index, id));
+        }
+
+        let mut responses = Vec::with_capacity(ids.len());
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, Some(response))) => responses.push((index, response)),
+                Ok((_, None)) => {}
+                Err(e) => {
+                    tracing::error!(error = ?e, "batched request failed");
+                    if let Some((index, Some(id))) = ids.remove(&e.id()) {
+                        responses.push((
+                            index,
+                            RpcResponse::error(
+                                id,
+                                RpcError::new(ErrorCode::InternalError, "internal error"),
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        responses.sort_by_key(|(index, _)| *index);
+        responses
+            .into_iter()
+            .map(|(_, response)| response)
+            .collect()
+    }
 }
 
 impl hyper::service::Service<hyper::Request<hyper::body::Incoming>> for RpcClient {
@@ -165,25 +374,322 @@ impl hyper::service::Service<hyper::Request<hyper::body::Incoming>> for RpcClien
         let handler = self.clone();
         let future = async move {
             match (req.method(), req.uri().path()) {
-                (&hyper::Method::OPTIONS, _) => Ok(RpcResponse::ok(u64::MAX).into()),
-                (_, "/") => match req.collect().await {
-                    Ok(body) => {
-                        let whole_body = body.aggregate();
-                        match serde_json::from_reader(whole_body.reader()) {
-                            Ok(req) => Ok(handler.handle(req).await.into()),
-
-                            Err(e) => Ok(RpcResponse::error(u64::MAX, &e.to_string()).into()),
+                (&hyper::Method::OPTIONS, _) => Ok(RpcResponse::ok(RpcId::Null).into()),
+                (_, "/") => {
+                    let headers = req.headers().clone();
+                    match req.collect().await {
+                        Ok(body) => {
+                            let body = body.to_bytes();
+                            let scope = match handler.authenticate(&headers, &body) {
+                                Ok(scope) => scope,
+                                Err(e) => {
+                                    return Ok(RpcResponse::error(RpcId::from_body(&body), e).into())
+                                }
+                            };
+                            match serde_json::from_slice(&body) {
+                                Ok(body) => Ok(handler.handle_body(body, scope).await.into()),
+
+                                Err(e) => Ok(RpcResponse::error(
+                                    RpcId::Null,
+                                    RpcError::new(ErrorCode::ParseError, e.to_string()),
+                                )
+                                .into()),
+                            }
+                        }
+                        Err(e) => {
+                            tracing::error!("error reading request body: {:?}", e);
+                            Ok(RpcResponse::error(
+                                RpcId::Null,
+                                RpcError::new(ErrorCode::InvalidRequest, e.to_string()),
+                            )
+                            .into())
                         }
                     }
-                    Err(e) => {
-                        tracing::error!("error reading request body: {:?}", e);
-                        Ok(RpcResponse::error(u64::MAX, &e.to_string()).into())
-                    }
-                },
-                _ => Ok(RpcResponse::error(u64::MAX, "not found").into()),
+                }
+                _ => Ok(RpcResponse::error(
+                    RpcId::Null,
+                    RpcError::new(ErrorCode::InvalidRequest, "not found"),
+                )
+                .into()),
             }
         };
 
         Box::pin(future)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use x_link_wallet::{exports::ExportRecord, keygen::KeyGen};
+
+    use super::*;
+
+    const SECRET: &[u8; 64] = b"What the fuck did you just fucking say about me you little bitch";
+
+    fn client() -> RpcClient {
+        RpcClient::new(
+            WalletSource::Local(Arc::new(KeyGen::from(*SECRET))),
+            Exports::default(),
+            WalletCache::new(16, std::time::Duration::from_secs(60)),
+        )
+        .with_max_batch_size(3)
+    }
+
+    fn get_account(id: Option<u64>, twitter_id: &str) -> serde_json::Value {
+        let mut request = json!({
+            "jsonrpc": "2.0",
+            "method": "getAccount",
+            "params": { "twitterId": twitter_id }
+        });
+        if let Some(id) = id {
+            request["id"] = json!(id);
+        }
+        request
+    }
+
+    fn batch(reply: RpcReply) -> serde_json::Value {
+        match reply {
+            RpcReply::Batch(responses) => serde_json::to_value(responses).unwrap(),
+            _ => panic!("Expected a batch reply"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exported_wallet() {
+        let secret_file = std::env::temp_dir()
+            .join(format!("x-link-client-exports-{}", std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        let client = RpcClient::new(
+            WalletSource::Local(Arc::new(KeyGen::from(*SECRET))),
+            Exports::load([secret_file.as_str()]).unwrap(),
+            WalletCache::new(16, std::time::Duration::from_secs(60)),
+        );
+
+        let response = |request: serde_json::Value| {
+            let client = client.clone();
+            async move {
+                match client.handle_body(request, Scope::Admin).await {
+                    RpcReply::Single(response) => serde_json::to_value(response).unwrap(),
+                    _ => panic!("Expected a single reply"),
+                }
+            }
+        };
+
+        let mut request = get_account(Some(1), "1722992406616756224");
+        assert!(response(request.clone()).await["result"]
+            .get("exportedAt")
+            .is_none());
+
+        // Exported while the client runs, with the wallet already cached
+        Exports::append(
+            &secret_file,
+            &ExportRecord {
+                twitter_id: 1722992406616756224,
+                pubkey: "ENuzcbEgZq9j9BQCSgsFfvnMeX8aY7xGDizGyf29eByN".to_string(),
+                scheme: 1,
+                format: "json".to_string(),
+                exported_at: 1_700_000_000,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            response(request.clone()).await["result"]["exportedAt"],
+            1_700_000_000
+        );
+        // Sub-wallets have keys of their own
+        request["params"]["walletIndex"] = json!(1);
+        assert!(response(request).await["result"]
+            .get("exportedAt")
+            .is_none());
+
+        let buy = json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "buy",
+            "params": {
+                "twitterId": "1722992406616756224",
+                "tokenId": "So11111111111111111111111111111111111111112",
+                "amount": 100
+            }
+        });
+        let error = &response(buy).await["error"];
+        assert_eq!(error["code"], -32008);
+        assert_eq!(error["data"]["exportedAt"], 1_700_000_000);
+
+        std::fs::remove_file(Exports::path_for(&secret_file)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_batch() {
+        let client = client();
+
+        let responses = batch(
+            client
+                .handle_body(
+                    json!([
+                        get_account(Some(7), "1722992406616756224"),
+                        // A notification, not answered
+                        get_account(None, "1722992406616756224"),
+                        get_account(Some(3), "0"),
+                    ]),
+                    Scope::Admin,
+                )
+                .await,
+        );
+        let responses = responses.as_array().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], 7);
+        assert_eq!(
+            responses[0]["result"]["pubkey"],
+            "ENuzcbEgZq9j9BQCSgsFfvnMeX8aY7xGDizGyf29eByN"
+        );
+        assert_eq!(responses[1]["id"], 3);
+        assert_eq!(responses[1]["error"]["code"], -32602);
+
+        assert!(matches!(
+            client
+                .handle_body(
+                    json!([get_account(None, "1722992406616756224")]),
+                    Scope::Admin
+                )
+                .await,
+            RpcReply::Empty
+        ));
+        assert!(matches!(
+            client
+                .handle_body(get_account(None, "1722992406616756224"), Scope::Admin)
+                .await,
+            RpcReply::Empty
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_request_ids() {
+        let client = client();
+        let request = |id: serde_json::Value, twitter_id: &str| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "getAccount",
+                "params": { "twitterId": twitter_id }
+            })
+        };
+
+        let responses = batch(
+            client
+                .handle_body(
+                    json!([
+                        request(
+                            json!("0b9f5e4c-3a7d-4a5e-9c1e-2f0a6b8d7c31"),
+                            "1722992406616756224"
+                        ),
+                        request(json!(null), "1722992406616756224"),
+                        request(json!("bad"), "0"),
+                    ]),
+                    Scope::Admin,
+                )
+                .await,
+        );
+        assert_eq!(
+            responses,
+            json!([
+                {
+                    "jsonrpc": "2.0",
+                    "id": "0b9f5e4c-3a7d-4a5e-9c1e-2f0a6b8d7c31",
+                    "result": responses[0]["result"]
+                },
+                { "jsonrpc": "2.0", "id": null, "result": responses[1]["result"] },
+                { "jsonrpc": "2.0", "id": "bad", "error": responses[2]["error"] }
+            ])
+        );
+        assert!(responses[0]["result"].is_object());
+        assert!(responses[1]["result"].is_object());
+        assert_eq!(responses[2]["error"]["code"], -32602);
+
+        // A failing notification isn't answered either
+        assert!(matches!(
+            client
+                .handle_body(get_account(None, "0"), Scope::Admin)
+                .await,
+            RpcReply::Empty
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_scope() {
+        let client = client();
+        let buy = |id: Option<u64>| {
+            let mut request = json!({
+                "jsonrpc": "2.0",
+                "method": "buy",
+                "params": {
+                    "twitterId": "1722992406616756224",
+                    "tokenId": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                    "amount": 1000
+                }
+            });
+            if let Some(id) = id {
+                request["id"] = json!(id);
+            }
+            request
+        };
+
+        let responses = batch(
+            client
+                .handle_body(
+                    json!([
+                        get_account(Some(1), "1722992406616756224"),
+                        buy(Some(2)),
+                        // Refused without an answer
+                        buy(None),
+                    ]),
+                    Scope::Read,
+                )
+                .await,
+        );
+        let responses = responses.as_array().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert!(responses[0]["result"].is_object());
+        assert_eq!(responses[1]["id"], 2);
+        assert_eq!(responses[1]["error"]["code"], -32007);
+
+        match client.handle_body(buy(Some(3)), Scope::Read).await {
+            RpcReply::Single(response) => {
+                let response: hyper::Response<Full<Bytes>> = response.into();
+                assert_eq!(response.status(), hyper::StatusCode::FORBIDDEN);
+            }
+            _ => panic!("Expected a single error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_batch() {
+        let client = client();
+
+        for body in [json!([]), json!([1, 2, 3, 4])] {
+            match client.handle_body(body, Scope::Admin).await {
+                RpcReply::Single(response) => {
+                    assert_eq!(response.id, RpcId::Null);
+                    assert_eq!(response.error.unwrap().code, ErrorCode::InvalidRequest);
+                }
+                _ => panic!("Expected a single error"),
+            }
+        }
+
+        // Each invalid element is answered on its own
+        let responses = batch(
+            client
+                .handle_body(json!([1, { "id": 5 }]), Scope::Admin)
+                .await,
+        );
+        let responses = responses.as_array().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], serde_json::Value::Null);
+        assert_eq!(responses[0]["error"]["code"], -32600);
+        assert_eq!(responses[1]["id"], 5);
+        assert_eq!(responses[1]["error"]["code"], -32600);
+    }
+}
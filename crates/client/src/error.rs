@@ -3,6 +3,15 @@ pub enum Error {
     #[error("RPC error: {0}")]
     Generic(String),
 
+    #[error("Derivation error: {0}")]
+    Derivation(String),
+
+    #[error("X-Link Signer error: {0}")]
+    Signer(#[from] x_link_signer::error::Error),
+
     #[error("X-Link Solana error: {0}")]
     Client(#[from] x_link_solana::error::Error),
+
+    #[error("Wallet key was exported at {0}, it may be controlled elsewhere")]
+    Exported(u64),
 }
@@ -1,7 +1,10 @@
+pub mod auth;
+pub mod cache;
 pub mod cli;
 pub mod client;
 pub mod error;
 pub mod message;
+pub mod wallet;
 
 mod deserialize;
 mod serialize;
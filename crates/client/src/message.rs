@@ -1,55 +1,134 @@
 use crate::deserialize::{pubkey_deserialize, signature_deserialize};
+use crate::error::Error;
 use crate::serialize::{pubkey_serialize, signature_serialize};
 use http_body_util::Full;
 use hyper::body::Bytes;
 use serde::{Deserialize, Serialize};
 use solana_sdk::{pubkey::Pubkey, signature::Signature};
-use x_link_solana::QuoteResponse;
-use x_link_types::account::Account;
+use x_link_solana::{
+    backend::trench::{Launch, TokenMetadata},
+    QuoteResponse,
+};
+use x_link_types::{account::AccountInfo, chain::Chain, twitter::TwitterId};
+
+/// A request id as the client sent it: a number, a string or `null`. It is
+/// echoed back unchanged in the response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(untagged)]
+pub enum RpcId {
+    Number(serde_json::Number),
+    String(String),
+    /// An explicit `null`, or an id that couldn't be read
+    #[default]
+    Null,
+}
+
+impl RpcId {
+    /// The id of a raw request, `None` if it has none and so is a
+    /// notification. Ids of the wrong type read as `Null`.
+    pub fn from_request(request: &serde_json::Value) -> Option<Self> {
+        request
+            .get("id")
+            .map(|id| serde_json::from_value(id.clone()).unwrap_or_default())
+    }
+
+    /// The id of a raw body, for answering it before it's handled. `Null`
+    /// for batches and anything that isn't JSON.
+    pub fn from_body(body: &[u8]) -> Self {
+        serde_json::from_slice::<serde_json::Value>(body)
+            .ok()
+            .and_then(|request| Self::from_request(&request))
+            .unwrap_or_default()
+    }
+}
+
+impl From<u64> for RpcId {
+    fn from(id: u64) -> Self {
+        Self::Number(id.into())
+    }
+}
+
+impl From<String> for RpcId {
+    fn from(id: String) -> Self {
+        Self::String(id)
+    }
+}
+
+impl From<&str> for RpcId {
+    fn from(id: &str) -> Self {
+        Self::String(id.to_string())
+    }
+}
+
+impl PartialEq<u64> for RpcId {
+    fn eq(&self, other: &u64) -> bool {
+        matches!(self, Self::Number(id) if id.as_u64() == Some(*other))
+    }
+}
+
+/// Deserializes a present `id`, `null` included, as `Some` so it can be
+/// told apart from a missing one.
+fn present_id<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<RpcId>, D::Error> {
+    RpcId::deserialize(deserializer).map(Some)
+}
 
 #[derive(Serialize, Debug)]
 pub struct RpcRequest {
     pub jsonrpc: String,
-    pub id: u64,
+    /// Absent for notifications, which get no response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<RpcId>,
     pub method: String,
     #[serde(flatten)]
     pub params: RpcParams,
 }
 
-impl<'de> Deserialize<'de> for RpcRequest {
-    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        use serde::de::Error;
+impl RpcRequest {
+    /// Parse a request, telling an invalid request, an unknown method and
+    /// invalid params apart.
+    pub fn from_value(value: serde_json::Value) -> Result<Self, RpcError> {
         #[derive(Deserialize, Debug)]
         struct RawRequest {
             jsonrpc: String,
-            id: u64,
+            #[serde(default, deserialize_with = "present_id")]
+            id: Option<RpcId>,
             method: String,
+            #[serde(default)]
             params: serde_json::Value,
         }
 
-        let raw = RawRequest::deserialize(deserializer)?;
+        let raw = serde_json::from_value::<RawRequest>(value).map_err(|e| {
+            RpcError::new(ErrorCode::InvalidRequest, format!("invalid request: {}", e))
+        })?;
+        if raw.jsonrpc != "2.0" {
+            return Err(RpcError::new(
+                ErrorCode::InvalidRequest,
+                format!("unsupported jsonrpc version: {:?}", raw.jsonrpc),
+            ));
+        }
 
-        let params = match raw.method.as_str() {
-            "buy" => serde_json::from_value(raw.params)
-                .map(RpcParams::Buy)
-                .map_err(|e| D::Error::custom(format!("invalid buy params: {}", e)))?,
-            "sell" => serde_json::from_value(raw.params)
-                .map(RpcParams::Sell)
-                .map_err(|e| D::Error::custom(format!("invalid sell params: {}", e)))?,
-            "create" => serde_json::from_value(raw.params)
-                .map(RpcParams::Create)
-                .map_err(|e| D::Error::custom(format!("invalid create params: {}", e)))?,
-            "getAccount" => serde_json::from_value(raw.params)
-                .map(RpcParams::GetAccount)
-                .map_err(|e| D::Error::custom(format!("invalid getAccount params: {}", e)))?,
-            "quote" => serde_json::from_value(raw.params)
-                .map(RpcParams::Quote)
-                .map_err(|e| D::Error::custom(format!("invalid quote params: {}", e)))?,
-            _ => return Err(D::Error::custom(format!("invalid method: {}", raw.method))),
+        let invalid_params = |e: serde_json::Error| {
+            RpcError::new(
+                ErrorCode::InvalidParams,
+                format!("invalid {} params: {}", raw.method, e),
+            )
         };
+        let params = match raw.method.as_str() {
+            "buy" => serde_json::from_value(raw.params).map(RpcParams::Buy),
+            "sell" => serde_json::from_value(raw.params).map(RpcParams::Sell),
+            "create" => serde_json::from_value(raw.params).map(RpcParams::Create),
+            "getAccount" => serde_json::from_value(raw.params).map(RpcParams::GetAccount),
+            "quote" => serde_json::from_value(raw.params).map(RpcParams::Quote),
+            _ => {
+                return Err(RpcError::new(
+                    ErrorCode::MethodNotFound,
+                    format!("method not found: {}", raw.method),
+                ))
+            }
+        }
+        .map_err(invalid_params)?;
 
         Ok(RpcRequest {
             jsonrpc: raw.jsonrpc,
@@ -60,11 +139,23 @@ impl<'de> Deserialize<'de> for RpcRequest {
     }
 }
 
+impl<'de> Deserialize<'de> for RpcRequest {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+        Self::from_value(serde_json::Value::deserialize(deserializer)?)
+            .map_err(|e| D::Error::custom(e.message))
+    }
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcResponse {
     pub jsonrpc: String,
-    pub id: u64,
+    /// `null` when the request's id couldn't be read
+    pub id: RpcId,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub result: Option<RpcResult>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -73,21 +164,24 @@ pub struct RpcResponse {
 
 impl From<Box<dyn std::error::Error>> for RpcResponse {
     fn from(e: Box<dyn std::error::Error>) -> Self {
-        Self::error(u64::MAX, &e.to_string())
+        Self::error(
+            RpcId::Null,
+            RpcError::new(ErrorCode::InternalError, e.to_string()),
+        )
     }
 }
 
 impl RpcResponse {
-    pub fn error(id: u64, message: &str) -> Self {
+    pub fn error(id: RpcId, error: impl Into<RpcError>) -> Self {
         Self {
             jsonrpc: "2.0".to_string(),
             id,
             result: None,
-            error: Some(RpcError::Error(message.to_string())),
+            error: Some(error.into()),
         }
     }
 
-    pub fn ok(id: u64) -> Self {
+    pub fn ok(id: RpcId) -> Self {
         Self {
             jsonrpc: "2.0".to_string(),
             id,
@@ -96,7 +190,7 @@ impl RpcResponse {
         }
     }
 
-    pub fn with_account(mut self, account: Account) -> Self {
+    pub fn with_account(mut self, account: AccountInfo) -> Self {
         self.result = Some(RpcResult::Account(account));
         self
     }
@@ -110,6 +204,14 @@ impl RpcResponse {
         self.result = Some(RpcResult::Quote(quote));
         self
     }
+
+    pub fn with_launch(mut self, launch: Launch) -> Self {
+        self.result = Some(RpcResult::Launch {
+            mint: launch.mint,
+            signature: launch.signature,
+        });
+        self
+    }
 }
 
 #[derive(Serialize)]
@@ -118,16 +220,139 @@ impl RpcResponse {
 pub enum RpcResult {
     #[serde(rename = "ok")]
     Ok,
-    Account(Account),
+    Account(AccountInfo),
     #[serde(serialize_with = "signature_serialize")]
     Signature(Signature),
     Quote(QuoteResponse),
+    Launch {
+        #[serde(serialize_with = "pubkey_serialize")]
+        mint: Pubkey,
+        #[serde(serialize_with = "signature_serialize")]
+        signature: Signature,
+    },
 }
 
-#[derive(Serialize)]
-#[serde(untagged)]
-pub enum RpcError {
-    Error(String),
+/// JSON-RPC error codes: the standard ones, and x-link's own for failures a
+/// user can act on in the range reserved for implementations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The body isn't JSON
+    ParseError,
+    /// Not a JSON-RPC 2.0 request object, or a batch that can't be handled
+    InvalidRequest,
+    MethodNotFound,
+    InvalidParams,
+    InternalError,
+    /// The wallet can't pay for the trade or its fees
+    InsufficientBalance,
+    /// The price moved beyond the slippage tolerance before the swap landed
+    SlippageExceeded,
+    /// The token isn't tradable or has no route
+    UnknownToken,
+    /// The user's wallet couldn't be derived
+    DerivationFailed,
+    /// The signer daemon refused to sign
+    SignerRefused,
+    /// No valid API key, answered with `401 Unauthorized`
+    Unauthorized,
+    /// The API key's scope doesn't cover the method
+    Forbidden,
+    /// The wallet's key was exported and the trade didn't allow it
+    WalletExported,
+    /// The signer daemon couldn't be reached, the request may be retried
+    SignerUnavailable,
+}
+
+impl ErrorCode {
+    pub fn code(&self) -> i64 {
+        match self {
+            Self::ParseError => -32700,
+            Self::InvalidRequest => -32600,
+            Self::MethodNotFound => -32601,
+            Self::InvalidParams => -32602,
+            Self::InternalError => -32603,
+            Self::InsufficientBalance => -32001,
+            Self::SlippageExceeded => -32002,
+            Self::UnknownToken => -32003,
+            Self::DerivationFailed => -32004,
+            Self::SignerRefused => -32005,
+            Self::Unauthorized => -32006,
+            Self::Forbidden => -32007,
+            Self::WalletExported => -32008,
+            Self::SignerUnavailable => -32009,
+        }
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.code())
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct RpcError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl RpcError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+impl From<&x_link_solana::error::Error> for RpcError {
+    fn from(e: &x_link_solana::error::Error) -> Self {
+        use solana_sdk::signer::SignerError;
+        use x_link_solana::error::Error;
+        let code = match e {
+            Error::InsufficientBalance(_) => ErrorCode::InsufficientBalance,
+            Error::SlippageExceeded => ErrorCode::SlippageExceeded,
+            Error::UnknownToken(_) => ErrorCode::UnknownToken,
+            Error::InvalidMetadata(_) => ErrorCode::InvalidParams,
+            // Remote wallets report the signer's failures this way
+            Error::Signer(SignerError::Connection(_)) => ErrorCode::SignerUnavailable,
+            Error::Signer(_) => ErrorCode::SignerRefused,
+            Error::Generic(_) | Error::Client(_) | Error::SolanaClient(_) => {
+                ErrorCode::InternalError
+            }
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<&Error> for RpcError {
+    fn from(e: &Error) -> Self {
+        use x_link_signer::error::Error as SignerError;
+        let code = match e {
+            Error::Client(e) => return e.into(),
+            Error::Exported(exported_at) => {
+                return Self::new(ErrorCode::WalletExported, e.to_string())
+                    .with_data(serde_json::json!({ "exportedAt": exported_at }))
+            }
+            Error::Derivation(_) => ErrorCode::DerivationFailed,
+            Error::Signer(SignerError::Refused(_) | SignerError::Policy(_)) => {
+                ErrorCode::SignerRefused
+            }
+            Error::Signer(SignerError::Io(_)) => ErrorCode::SignerUnavailable,
+            Error::Signer(SignerError::Generic(_) | SignerError::Json(_)) | Error::Generic(_) => {
+                ErrorCode::InternalError
+            }
+        };
+        Self::new(code, e.to_string())
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -144,27 +369,45 @@ pub enum RpcParams {
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct GetAccountParams {
-    pub twitter_id: u64,
+    pub twitter_id: TwitterId,
+    /// Which of the user's wallets to use, their main wallet if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet_index: Option<u32>,
+    /// Chain of the wallet, Solana if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain: Option<Chain>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct BuyParams {
-    pub twitter_id: u64,
+    pub twitter_id: TwitterId,
+    /// Which of the user's wallets to use, their main wallet if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet_index: Option<u32>,
     #[serde(deserialize_with = "pubkey_deserialize")]
     #[serde(serialize_with = "pubkey_serialize")]
     pub token_id: Pubkey,
     pub amount: u64,
+    /// Trade even if the wallet's key was exported
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_exported: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SellParams {
-    pub twitter_id: u64,
+    pub twitter_id: TwitterId,
+    /// Which of the user's wallets to use, their main wallet if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet_index: Option<u32>,
     #[serde(deserialize_with = "pubkey_deserialize")]
     #[serde(serialize_with = "pubkey_serialize")]
     pub token_id: Pubkey,
     pub amount: u64,
+    /// Trade even if the wallet's key was exported
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_exported: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -182,9 +425,16 @@ pub struct QuoteParams {
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateParams {
-    pub twitter_id: u64,
+    pub twitter_id: TwitterId,
+    /// Which of the user's wallets to use, their main wallet if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet_index: Option<u32>,
+    /// Lamports of the new token to buy right away, none if zero
     pub amount: u64,
     pub token: TokenParams,
+    /// Trade even if the wallet's key was exported
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_exported: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -192,19 +442,65 @@ pub struct TokenParams {
     pub name: String,
     pub ticker: String,
     pub uri: String,
-    pub description: String,
+    /// Accepted for older callers but ignored, a description is only
+    /// published in the JSON document at `uri`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl From<TokenParams> for TokenMetadata {
+    fn from(token: TokenParams) -> Self {
+        Self {
+            name: token.name,
+            symbol: token.ticker,
+            uri: token.uri,
+        }
+    }
+}
+
+/// What a request body is answered with.
+pub enum RpcReply {
+    Single(RpcResponse),
+    /// One response per batched request, notifications excluded
+    Batch(Vec<RpcResponse>),
+    /// Every request was a notification
+    Empty,
+}
+
+impl From<RpcReply> for hyper::Response<Full<Bytes>> {
+    fn from(reply: RpcReply) -> Self {
+        match reply {
+            RpcReply::Single(response) => response.into(),
+            // Each response carries its own error, the batch as a whole succeeded
+            RpcReply::Batch(responses) => hyper::Response::new(Full::from(
+                serde_json::to_vec(&responses).expect("error serializing response"),
+            )),
+            RpcReply::Empty => {
+                let mut response = hyper::Response::new(Full::default());
+                *response.status_mut() = hyper::StatusCode::NO_CONTENT;
+                response
+            }
+        }
+    }
 }
 
-// TODO: Expand this with more expressive errors
 impl From<RpcResponse> for hyper::Response<Full<Bytes>> {
     fn from(res: RpcResponse) -> Self {
         let mut response = hyper::Response::new(Full::from(
             serde_json::to_vec(&res).expect("error serializing response"),
         ));
-        *response.status_mut() = match (res.result, res.error) {
-            (Some(RpcResult::Ok), None) => hyper::StatusCode::OK,
-            (_, Some(RpcError::Error(_))) => hyper::StatusCode::BAD_REQUEST,
-            _ => hyper::StatusCode::INTERNAL_SERVER_ERROR,
+        // Errors are read from the body like any other response, only a
+        // missing or insufficient API key changes the status
+        *response.status_mut() = match res.error.map(|error| error.code) {
+            Some(ErrorCode::Unauthorized) => {
+                response.headers_mut().insert(
+                    hyper::header::WWW_AUTHENTICATE,
+                    hyper::header::HeaderValue::from_static("Bearer"),
+                );
+                hyper::StatusCode::UNAUTHORIZED
+            }
+            Some(ErrorCode::Forbidden) => hyper::StatusCode::FORBIDDEN,
+            _ => hyper::StatusCode::OK,
         };
         response
     }
@@ -232,7 +528,7 @@ mod tests {
         let request: RpcRequest = serde_json::from_value(request_json.clone()).unwrap();
 
         assert_eq!(request.jsonrpc, "2.0");
-        assert_eq!(request.id, 1);
+        assert_eq!(request.id, Some(RpcId::from(1)));
         assert_eq!(request.method, "buy");
 
         match request.params {
@@ -240,6 +536,7 @@ mod tests {
                 assert_eq!(params.twitter_id, 123456);
                 assert_eq!(params.token_id, token_id);
                 assert_eq!(params.amount, 100);
+                assert!(!params.allow_exported);
             }
             _ => panic!("Expected Buy params"),
         }
@@ -266,7 +563,7 @@ mod tests {
         let request: RpcRequest = serde_json::from_value(request_json.clone()).unwrap();
 
         assert_eq!(request.jsonrpc, "2.0");
-        assert_eq!(request.id, 2);
+        assert_eq!(request.id, Some(RpcId::from(2)));
         assert_eq!(request.method, "sell");
 
         match request.params {
@@ -304,7 +601,7 @@ mod tests {
         let request: RpcRequest = serde_json::from_value(request_json.clone()).unwrap();
 
         assert_eq!(request.jsonrpc, "2.0");
-        assert_eq!(request.id, 3);
+        assert_eq!(request.id, Some(RpcId::from(3)));
         assert_eq!(request.method, "create");
 
         match request.params {
@@ -314,7 +611,10 @@ mod tests {
                 assert_eq!(params.token.name, "Test Token");
                 assert_eq!(params.token.ticker, "TEST");
                 assert_eq!(params.token.uri, "https://example.com/token");
-                assert_eq!(params.token.description, "Test token description");
+                assert_eq!(
+                    params.token.description.as_deref(),
+                    Some("Test token description")
+                );
             }
             _ => panic!("Expected Create params"),
         }
@@ -322,6 +622,167 @@ mod tests {
         // Test serialization
         let serialized = serde_json::to_value(request).unwrap();
         assert_eq!(serialized, request_json);
+
+        // The description can be left out
+        let token: TokenParams = serde_json::from_value(json!({
+            "name": "Test Token",
+            "ticker": "TEST",
+            "uri": "https://example.com/token"
+        }))
+        .unwrap();
+        assert_eq!(token.description, None);
+    }
+
+    #[test]
+    fn test_launch_response() {
+        let launch = Launch {
+            mint: Pubkey::new_unique(),
+            signature: Signature::from([7; 64]),
+        };
+        assert_eq!(
+            serde_json::to_value(RpcResponse::ok(3.into()).with_launch(launch)).unwrap(),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "result": {
+                    "mint": launch.mint.to_string(),
+                    "signature": launch.signature.to_string()
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_wallet_index() {
+        let request: RpcRequest = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "id": 4,
+            "method": "getAccount",
+            "params": { "twitterId": 123456, "walletIndex": 2 }
+        }))
+        .unwrap();
+        match request.params {
+            RpcParams::GetAccount(ref params) => assert_eq!(params.wallet_index, Some(2)),
+            _ => panic!("Expected GetAccount params"),
+        }
+
+        let request: RpcRequest = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "id": 5,
+            "method": "getAccount",
+            "params": { "twitterId": 123456 }
+        }))
+        .unwrap();
+        match request.params {
+            RpcParams::GetAccount(ref params) => assert_eq!(params.wallet_index, None),
+            _ => panic!("Expected GetAccount params"),
+        }
+    }
+
+    #[test]
+    fn test_twitter_id() {
+        let request: RpcRequest = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "id": 8,
+            "method": "getAccount",
+            "params": { "twitterId": "1722992406616756224" }
+        }))
+        .unwrap();
+        match request.params {
+            RpcParams::GetAccount(ref params) => {
+                assert_eq!(params.twitter_id, 1722992406616756224)
+            }
+            _ => panic!("Expected GetAccount params"),
+        }
+
+        // Beyond 2^53 a JSON number may already have been rounded
+        assert!(serde_json::from_value::<RpcRequest>(json!({
+            "jsonrpc": "2.0",
+            "id": 9,
+            "method": "getAccount",
+            "params": { "twitterId": 1722992406616756224u64 }
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_notification() {
+        let request_json = json!({
+            "jsonrpc": "2.0",
+            "method": "getAccount",
+            "params": { "twitterId": "123456" }
+        });
+        let request: RpcRequest = serde_json::from_value(request_json.clone()).unwrap();
+        assert_eq!(request.id, None);
+        assert_eq!(serde_json::to_value(&request).unwrap(), request_json);
+    }
+
+    #[test]
+    fn test_request_id() {
+        const UUID: &str = "0b9f5e4c-3a7d-4a5e-9c1e-2f0a6b8d7c31";
+        let request = |id: serde_json::Value| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "getAccount",
+                "params": { "twitterId": "123456" }
+            })
+        };
+
+        for (id, expected) in [
+            (json!(UUID), RpcId::from(UUID)),
+            (json!(""), RpcId::from("")),
+            (json!(-1), RpcId::Number((-1).into())),
+            (json!(u64::MAX), RpcId::from(u64::MAX)),
+            (json!(null), RpcId::Null),
+        ] {
+            let request_json = request(id);
+            let parsed: RpcRequest = serde_json::from_value(request_json.clone()).unwrap();
+            assert_eq!(parsed.id, Some(expected.clone()));
+            assert_eq!(serde_json::to_value(&parsed).unwrap(), request_json);
+            assert_eq!(RpcId::from_request(&request_json), Some(expected));
+        }
+
+        assert_eq!(
+            RpcRequest::from_value(request(json!({ "a": 1 })))
+                .unwrap_err()
+                .code,
+            ErrorCode::InvalidRequest
+        );
+        assert_eq!(RpcId::from_request(&request(json!([1]))), Some(RpcId::Null));
+        assert_eq!(RpcId::from_request(&json!({ "jsonrpc": "2.0" })), None);
+
+        assert_eq!(RpcId::from_body(br#"{"id":7,"method":"nope"}"#), 7);
+        assert_eq!(RpcId::from_body(br#"[{"id":7}]"#), RpcId::Null);
+        assert_eq!(RpcId::from_body(b"{"), RpcId::Null);
+
+        assert_eq!(
+            serde_json::to_value(RpcResponse::ok(UUID.into())).unwrap()["id"],
+            json!(UUID)
+        );
+    }
+
+    #[test]
+    fn test_chain() {
+        let request: RpcRequest = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "id": 6,
+            "method": "getAccount",
+            "params": { "twitterId": 123456, "chain": "evm" }
+        }))
+        .unwrap();
+        match request.params {
+            RpcParams::GetAccount(ref params) => assert_eq!(params.chain, Some(Chain::Evm)),
+            _ => panic!("Expected GetAccount params"),
+        }
+
+        assert!(serde_json::from_value::<RpcRequest>(json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "getAccount",
+            "params": { "twitterId": 123456, "chain": "bitcoin" }
+        }))
+        .is_err());
     }
 
     #[test]
@@ -334,7 +795,7 @@ mod tests {
 
         let response = RpcResponse {
             jsonrpc: "2.0".to_string(),
-            id: 1,
+            id: 1.into(),
             result: Some(RpcResult::Ok),
             error: None,
         };
@@ -348,17 +809,111 @@ mod tests {
         let expected_json = json!({
             "jsonrpc": "2.0",
             "id": 1,
-            "error": "Operation failed"
+            "error": { "code": -32001, "message": "Operation failed" }
         });
 
         let response = RpcResponse {
             jsonrpc: "2.0".to_string(),
-            id: 1,
+            id: 1.into(),
             result: None,
-            error: Some(RpcError::Error("Operation failed".to_string())),
+            error: Some(RpcError::new(
+                ErrorCode::InsufficientBalance,
+                "Operation failed",
+            )),
         };
 
         let serialized = serde_json::to_value(&response).unwrap();
         assert_eq!(serialized, expected_json);
     }
+
+    #[test]
+    fn test_error_codes() {
+        let code = |value: serde_json::Value| RpcRequest::from_value(value).unwrap_err().code;
+
+        assert_eq!(code(json!([1, 2])), ErrorCode::InvalidRequest);
+        assert_eq!(
+            code(json!({ "jsonrpc": "1.0", "id": 1, "method": "quote" })),
+            ErrorCode::InvalidRequest
+        );
+        assert_eq!(
+            code(json!({ "jsonrpc": "2.0", "id": 1, "method": "transfer", "params": {} })),
+            ErrorCode::MethodNotFound
+        );
+        assert_eq!(
+            code(json!({ "jsonrpc": "2.0", "id": 1, "method": "getAccount", "params": {} })),
+            ErrorCode::InvalidParams
+        );
+
+        let error = RpcError::from(&Error::Client(
+            x_link_solana::error::Error::SlippageExceeded,
+        ));
+        assert_eq!(
+            serde_json::to_value(RpcResponse::error(RpcId::Null, error)).unwrap(),
+            json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": {
+                    "code": -32002,
+                    "message": "Slippage tolerance exceeded"
+                }
+            })
+        );
+        assert_eq!(
+            RpcError::from(&Error::Derivation("bad seed".to_string())).code,
+            ErrorCode::DerivationFailed
+        );
+
+        // A signer that is down isn't refusing anything
+        let down = || std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert_eq!(
+            RpcError::from(&Error::Signer(x_link_signer::error::Error::Io(down()))).code,
+            ErrorCode::SignerUnavailable
+        );
+        assert_eq!(
+            RpcError::from(&Error::Client(
+                solana_sdk::signer::SignerError::Connection(down().to_string()).into()
+            ))
+            .code,
+            ErrorCode::SignerUnavailable
+        );
+        assert_eq!(
+            RpcError::from(&Error::Signer(x_link_signer::error::Error::Policy(
+                "transfer".to_string()
+            )))
+            .code,
+            ErrorCode::SignerRefused
+        );
+        assert_eq!(
+            serde_json::to_value(RpcError::from(&Error::Exported(1_700_000_000))).unwrap(),
+            json!({
+                "code": -32008,
+                "message": "Wallet key was exported at 1700000000, it may be controlled elsewhere",
+                "data": { "exportedAt": 1_700_000_000 }
+            })
+        );
+    }
+
+    #[test]
+    fn test_status() {
+        let status = |code| {
+            hyper::Response::<Full<Bytes>>::from(RpcResponse::error(
+                RpcId::Null,
+                RpcError::new(code, "error"),
+            ))
+            .status()
+        };
+        for code in [
+            ErrorCode::InvalidParams,
+            ErrorCode::InternalError,
+            ErrorCode::InsufficientBalance,
+            ErrorCode::WalletExported,
+        ] {
+            assert_eq!(status(code), hyper::StatusCode::OK, "{:?}", code);
+        }
+        assert_eq!(
+            status(ErrorCode::Unauthorized),
+            hyper::StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(status(ErrorCode::Forbidden), hyper::StatusCode::FORBIDDEN);
+    }
 }
@@ -0,0 +1,269 @@
+use std::sync::Arc;
+
+use solana_sdk::{pubkey::Pubkey, signer::SignerError, transaction::Transaction};
+use x_link_signer::client::{RemoteKey, SignerClient};
+use x_link_solana::signer::WalletSigner;
+use x_link_types::{
+    account::{Account, AccountInfo},
+    chain::Chain,
+    twitter::TwitterId,
+};
+use x_link_wallet::{exports::Exports, keygen::KeyGen};
+
+use crate::error::Error;
+
+/// Where the RPC server gets its wallets from.
+#[derive(Clone)]
+pub enum WalletSource {
+    /// Derive keys in process from the seed
+    Local(Arc<KeyGen>),
+    /// Ask the signer daemon, the seed never enters this process
+    Signer(Arc<SignerClient>),
+}
+
+/// A twitter user's wallet, signing in process or through the signer daemon.
+pub enum Wallet {
+    Local(Account),
+    Remote(RemoteWallet),
+}
+
+/// A wallet whose key stays in the signer daemon.
+pub struct RemoteWallet {
+    pub twitter_id: TwitterId,
+    pub wallet_index: u32,
+    pub pubkey: Pubkey,
+    /// Version of the derivation scheme that produced `pubkey`
+    pub scheme: u8,
+    /// When the signer was asked for the wallet, in seconds since the Unix epoch
+    pub created_at: u64,
+    /// When the key was exported, as the signer's export log says
+    pub exported_at: Option<u64>,
+    signer: Arc<SignerClient>,
+}
+
+impl WalletSource {
+    /// Sub-wallet `wallet_index` of a twitter ID, 0 is the ID's main wallet.
+    pub async fn wallet(&self, twitter_id: TwitterId, wallet_index: u32) -> Result<Wallet, Error> {
+        match self {
+            Self::Local(keygen) => {
+                let (scheme, wallet) = keygen
+                    .derive_indexed(twitter_id.get(), wallet_index)
+                    .map_err(|e| Error::Derivation(e.to_string()))?;
+                Ok(Wallet::Local(
+                    Account::new(twitter_id.into(), wallet, scheme.version())
+                        .with_wallet_index(wallet_index),
+                ))
+            }
+            Self::Signer(signer) => {
+                let RemoteKey {
+                    pubkey,
+                    scheme,
+                    exported_at,
+                } = signer.pubkey(twitter_id.get(), wallet_index).await?;
+                Ok(Wallet::Remote(RemoteWallet {
+                    twitter_id,
+                    wallet_index,
+                    pubkey,
+                    scheme,
+                    exported_at,
+                    created_at: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map_or(0, |since| since.as_secs()),
+                    signer: signer.clone(),
+                }))
+            }
+        }
+    }
+
+    /// EVM address of sub-wallet `wallet_index` of a twitter ID. Nothing signs
+    /// for EVM wallets yet, so only their public side is returned.
+    pub async fn evm_account(
+        &self,
+        twitter_id: TwitterId,
+        wallet_index: u32,
+    ) -> Result<AccountInfo, Error> {
+        let address = match self {
+            Self::Local(keygen) => keygen
+                .derive_evm(twitter_id.get(), wallet_index)
+                .map_err(|e| Error::Derivation(e.to_string()))?,
+            Self::Signer(signer) => signer.evm_address(twitter_id.get(), wallet_index).await?,
+        };
+        Ok(AccountInfo::new(
+            twitter_id.into(),
+            wallet_index,
+            Chain::Evm,
+            address.to_string(),
+        ))
+    }
+}
+
+impl WalletSigner for RemoteWallet {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    async fn sign_transaction(
+        &self,
+        transaction: &mut Transaction,
+    ) -> Result<(), x_link_solana::error::Error> {
+        let signature = self
+            .signer
+            .sign_message(
+                self.twitter_id.get(),
+                self.wallet_index,
+                &transaction.message,
+            )
+            .await
+            .map_err(|e| match e {
+                x_link_signer::error::Error::Io(e) => SignerError::Connection(e.to_string()),
+                e => SignerError::Custom(e.to_string()),
+            })?;
+        if !signature.verify(self.pubkey.as_ref(), &transaction.message_data()) {
+            return Err(
+                SignerError::Custom("signer returned an invalid signature".to_string()).into(),
+            );
+        }
+
+        let position = transaction
+            .get_signing_keypair_positions(&[self.pubkey])
+            .map_err(|e| SignerError::Custom(e.to_string()))?[0]
+            .ok_or(SignerError::KeypairPubkeyMismatch)?;
+        transaction.signatures[position] = signature;
+        Ok(())
+    }
+}
+
+impl WalletSigner for Wallet {
+    fn pubkey(&self) -> Pubkey {
+        match self {
+            Self::Local(account) => WalletSigner::pubkey(account),
+            Self::Remote(wallet) => wallet.pubkey,
+        }
+    }
+
+    async fn sign_transaction(
+        &self,
+        transaction: &mut Transaction,
+    ) -> Result<(), x_link_solana::error::Error> {
+        match self {
+            Self::Local(account) => account.sign_transaction(transaction).await,
+            Self::Remote(wallet) => wallet.sign_transaction(transaction).await,
+        }
+    }
+}
+
+impl Wallet {
+    /// When the wallet's key was exported, if ever. Local wallets are looked
+    /// up in `exports`, remote ones in the signer's own log.
+    pub fn exported_at(&self, exports: &Exports) -> Option<u64> {
+        match self {
+            // Only main wallets can be exported
+            Self::Local(account) if account.wallet_index == 0 => account
+                .id
+                .twitter_id()
+                .and_then(|twitter_id| exports.get(twitter_id.get()))
+                .map(|record| record.exported_at),
+            Self::Local(_) => None,
+            Self::Remote(wallet) => wallet.exported_at,
+        }
+    }
+
+    /// Same for both variants.
+    pub fn info(&self) -> AccountInfo {
+        match self {
+            Self::Local(account) => account.info(),
+            Self::Remote(wallet) => AccountInfo::new(
+                wallet.twitter_id.into(),
+                wallet.wallet_index,
+                Chain::Solana,
+                wallet.pubkey.to_string(),
+            )
+            .with_scheme(wallet.scheme)
+            .with_created_at(wallet.created_at),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::hash::Hash;
+    use solana_system_interface::instruction as system_instruction;
+    use x_link_signer::{
+        policy::{wrapped_sol_account, Policy},
+        server::Server,
+    };
+
+    use super::*;
+
+    const SECRET: &[u8; 64] = b"What the fuck did you just fucking say about me you little bitch";
+    const ID: u64 = 1722992406616756224;
+
+    #[tokio::test]
+    async fn test_local_and_remote_agree() {
+        let socket =
+            std::env::temp_dir().join(format!("x-link-client-{}.sock", std::process::id()));
+        let path = socket.to_str().unwrap().to_string();
+        let server = Server::new(KeyGen::from(*SECRET), Policy::default());
+        tokio::spawn(async move { server.run(&path).await });
+        let id = TwitterId::new(ID).unwrap();
+
+        let local = WalletSource::Local(Arc::new(KeyGen::from(*SECRET)))
+            .wallet(id, 1)
+            .await
+            .unwrap();
+        let remote_source = WalletSource::Signer(Arc::new(SignerClient::new(&socket)));
+        let remote = loop {
+            match remote_source.wallet(id, 1).await {
+                Ok(wallet) => break wallet,
+                Err(Error::Signer(x_link_signer::error::Error::Io(_))) => {
+                    tokio::task::yield_now().await
+                }
+                Err(e) => panic!("{}", e),
+            }
+        };
+        assert_eq!(
+            local.info().with_created_at(0),
+            remote.info().with_created_at(0)
+        );
+
+        let pubkey = local.pubkey();
+        let mut transaction = Transaction::new_with_payer(
+            &[system_instruction::transfer(
+                &pubkey,
+                &wrapped_sol_account(&pubkey),
+                1,
+            )],
+            Some(&pubkey),
+        );
+        transaction.message.recent_blockhash = Hash::new_unique();
+        remote.sign_transaction(&mut transaction).await.unwrap();
+        assert!(transaction.verify().is_ok());
+
+        let local = WalletSource::Local(Arc::new(KeyGen::from(*SECRET)))
+            .evm_account(id, 1)
+            .await
+            .unwrap()
+            .with_created_at(0);
+        assert_eq!(
+            local,
+            remote_source
+                .evm_account(id, 1)
+                .await
+                .unwrap()
+                .with_created_at(0)
+        );
+        assert_eq!(
+            serde_json::to_value(&local).unwrap(),
+            serde_json::json!({
+                "platform": "twitter",
+                "id": ID.to_string(),
+                "walletIndex": 1,
+                "chain": "evm",
+                "pubkey": "0xbb8Cfed46a942e6A671a8E4D98746E7Cb170b04d",
+                "createdAt": 0,
+            })
+        );
+
+        std::fs::remove_file(&socket).unwrap();
+    }
+}
@@ -0,0 +1,42 @@
+use x_link_wallet::{args::SecretArgs, exports::Exports};
+
+use crate::{error::Error, policy::Policy, server::Server};
+
+#[derive(clap::Parser)]
+pub struct Args {
+    #[clap(flatten)]
+    secret: SecretArgs,
+
+    /// Unix socket to serve requests on
+    #[clap(long)]
+    socket: String,
+
+    /// JSON policy file, defaults to allowing the programs Jupiter swaps and trench launches call
+    #[clap(long)]
+    policy: Option<String>,
+}
+
+impl Args {
+    pub async fn run(&self) -> Result<(), Error> {
+        let policy = match &self.policy {
+            Some(path) => Policy::load(path)?,
+            None => Policy::default(),
+        };
+        let source = self
+            .secret
+            .source()
+            .map_err(|e| Error::Generic(e.to_string()))?
+            .ok_or_else(|| {
+                Error::Generic("one of --secret-file or --share-file is required".to_string())
+            })?;
+        let keygen = source
+            .load(&self.secret.passphrase.source())
+            .map_err(|e| Error::Generic(format!("error loading keygen: {}", e)))?;
+        let exports = Exports::load(source.files())
+            .map_err(|e| Error::Generic(format!("error loading export log: {}", e)))?;
+        Server::new(keygen, policy)
+            .with_exports(exports)
+            .run(&self.socket)
+            .await
+    }
+}
@@ -0,0 +1,191 @@
+use std::path::PathBuf;
+use std::str::FromStr as _;
+
+use solana_sdk::{message::Message, pubkey::Pubkey, signature::Signature};
+use tokio::io::BufReader;
+use tokio::net::UnixStream;
+use x_link_wallet::evm::EvmAddress;
+
+use crate::{
+    error::Error,
+    message::{read_line, write_line, Request, Response},
+};
+
+/// The public side of a wallet whose key stays in the signer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemoteKey {
+    pub pubkey: Pubkey,
+    /// Version of the derivation scheme that produced `pubkey`
+    pub scheme: u8,
+    /// When the key was exported with `x-link-wallet export`, if ever
+    pub exported_at: Option<u64>,
+}
+
+/// Talks to a [`Server`](crate::server::Server) over its Unix socket, one
+/// connection per request.
+#[derive(Debug, Clone)]
+pub struct SignerClient {
+    socket: PathBuf,
+}
+
+impl SignerClient {
+    pub fn new(socket: impl Into<PathBuf>) -> Self {
+        Self {
+            socket: socket.into(),
+        }
+    }
+
+    /// The address of a twitter ID's wallet, `wallet_index` 0 is the ID's
+    /// main wallet.
+    pub async fn pubkey(&self, twitter_id: u64, wallet_index: u32) -> Result<RemoteKey, Error> {
+        match self
+            .request(&Request::Pubkey {
+                twitter_id,
+                wallet_index,
+            })
+            .await?
+        {
+            Response::Pubkey {
+                pubkey,
+                scheme,
+                exported_at,
+            } => Ok(RemoteKey {
+                pubkey: Pubkey::from_str(&pubkey)
+                    .map_err(|e| Error::Generic(format!("invalid pubkey: {}", e)))?,
+                scheme,
+                exported_at,
+            }),
+            response => Err(Self::unexpected(response)),
+        }
+    }
+
+    /// The EVM address of a twitter ID's wallet, `wallet_index` 0 is the
+    /// ID's main wallet.
+    pub async fn evm_address(
+        &self,
+        twitter_id: u64,
+        wallet_index: u32,
+    ) -> Result<EvmAddress, Error> {
+        match self
+            .request(&Request::EvmAddress {
+                twitter_id,
+                wallet_index,
+            })
+            .await?
+        {
+            Response::EvmAddress { address } => EvmAddress::from_str(&address)
+                .map_err(|e| Error::Generic(format!("invalid EVM address: {}", e))),
+            response => Err(Self::unexpected(response)),
+        }
+    }
+
+    /// Sign `message` as the twitter ID's wallet, subject to the signer's policy.
+    pub async fn sign_message(
+        &self,
+        twitter_id: u64,
+        wallet_index: u32,
+        message: &Message,
+    ) -> Result<Signature, Error> {
+        match self
+            .request(&Request::sign_message(twitter_id, wallet_index, message))
+            .await?
+        {
+            Response::Signature { signature } => Signature::from_str(&signature)
+                .map_err(|e| Error::Generic(format!("invalid signature: {}", e))),
+            response => Err(Self::unexpected(response)),
+        }
+    }
+
+    async fn request(&self, request: &Request) -> Result<Response, Error> {
+        let (read, mut write) = UnixStream::connect(&self.socket).await?.into_split();
+        write_line(&mut write, request).await?;
+        let line = read_line(&mut BufReader::new(read))
+            .await?
+            .ok_or_else(|| Error::Generic("signer closed the connection".to_string()))?;
+        match serde_json::from_str(&line)? {
+            Response::Error { message } => Err(Error::Refused(message)),
+            response => Ok(response),
+        }
+    }
+
+    fn unexpected(response: Response) -> Error {
+        Error::Generic(format!("unexpected response: {:?}", response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_system_interface::instruction as system_instruction;
+    use x_link_wallet::keygen::KeyGen;
+
+    use super::*;
+    use crate::{
+        policy::{wrapped_sol_account, Policy},
+        server::Server,
+    };
+
+    const SECRET: &[u8; 64] = b"What the fuck did you just fucking say about me you little bitch";
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let socket =
+            std::env::temp_dir().join(format!("x-link-signer-{}.sock", std::process::id()));
+        let server = Server::new(KeyGen::from(*SECRET), Policy::default());
+        let path = socket.to_str().unwrap().to_string();
+        tokio::spawn(async move { server.run(&path).await });
+
+        let client = SignerClient::new(&socket);
+        let RemoteKey {
+            pubkey,
+            scheme,
+            exported_at,
+        } = loop {
+            match client.pubkey(1722992406616756224, 0).await {
+                Ok(result) => break result,
+                Err(Error::Io(_)) => tokio::task::yield_now().await,
+                Err(e) => panic!("{}", e),
+            }
+        };
+        assert_eq!(
+            pubkey.to_string(),
+            "ENuzcbEgZq9j9BQCSgsFfvnMeX8aY7xGDizGyf29eByN"
+        );
+        assert_eq!(scheme, 1);
+        assert_eq!(exported_at, None);
+        assert_eq!(
+            client
+                .evm_address(1722992406616756224, 0)
+                .await
+                .unwrap()
+                .to_string(),
+            "0xed1B0701536Db26BB2BC2059C9C2ada1aD21B235"
+        );
+
+        let message = Message::new(
+            &[system_instruction::transfer(
+                &pubkey,
+                &wrapped_sol_account(&pubkey),
+                1,
+            )],
+            Some(&pubkey),
+        );
+        let signature = client
+            .sign_message(1722992406616756224, 0, &message)
+            .await
+            .unwrap();
+        assert!(signature.verify(pubkey.as_ref(), &message.serialize()));
+
+        // Refused by the policy
+        let other = Pubkey::new_unique();
+        let message = Message::new(
+            &[system_instruction::transfer(&other, &pubkey, 1)],
+            Some(&other),
+        );
+        assert!(matches!(
+            client.sign_message(1722992406616756224, 0, &message).await,
+            Err(Error::Refused(_))
+        ));
+
+        std::fs::remove_file(&socket).unwrap();
+    }
+}
@@ -0,0 +1,17 @@
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Signer error: {0}")]
+    Generic(String),
+
+    #[error("Signer refused: {0}")]
+    Refused(String),
+
+    #[error("Policy violation: {0}")]
+    Policy(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
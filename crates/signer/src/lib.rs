@@ -0,0 +1,6 @@
+pub mod cli;
+pub mod client;
+pub mod error;
+pub mod message;
+pub mod policy;
+pub mod server;
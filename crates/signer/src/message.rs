@@ -0,0 +1,182 @@
+//! Wire format between the signer and its clients: one JSON object per line
+//! in each direction, one response for every request.
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::message::Message;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt as _, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _,
+};
+
+use crate::error::Error;
+
+/// Longest line either side accepts, comfortably above a base58 encoded
+/// transaction message.
+pub const MAX_LINE_LEN: usize = 16 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "method", content = "params", rename_all = "camelCase")]
+pub enum Request {
+    /// Derive the wallet address for a twitter ID
+    #[serde(rename_all = "camelCase")]
+    Pubkey {
+        twitter_id: u64,
+        /// Sub-wallet of the ID, absent for its main wallet
+        #[serde(default, skip_serializing_if = "is_main_wallet")]
+        wallet_index: u32,
+    },
+    /// Derive the EVM address for a twitter ID
+    #[serde(rename_all = "camelCase")]
+    EvmAddress {
+        twitter_id: u64,
+        #[serde(default, skip_serializing_if = "is_main_wallet")]
+        wallet_index: u32,
+    },
+    /// Sign a transaction message as the twitter ID's wallet
+    #[serde(rename_all = "camelCase")]
+    SignMessage {
+        twitter_id: u64,
+        #[serde(default, skip_serializing_if = "is_main_wallet")]
+        wallet_index: u32,
+        /// Base58 of the bincode serialized message
+        message: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Response {
+    /// Base58 address and the derivation scheme version that produced it
+    #[serde(rename_all = "camelCase")]
+    Pubkey {
+        pubkey: String,
+        scheme: u8,
+        /// When the wallet's key was exported, absent if it never was
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        exported_at: Option<u64>,
+    },
+    /// EIP-55 checksummed hex address
+    EvmAddress {
+        address: String,
+    },
+    /// Base58 signature over the message
+    Signature {
+        signature: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+fn is_main_wallet(wallet_index: &u32) -> bool {
+    *wallet_index == 0
+}
+
+impl Request {
+    pub fn sign_message(twitter_id: u64, wallet_index: u32, message: &Message) -> Self {
+        Self::SignMessage {
+            twitter_id,
+            wallet_index,
+            message: bs58::encode(message.serialize()).into_string(),
+        }
+    }
+}
+
+/// Decode a message sent with [`Request::sign_message`].
+pub fn decode_message(message: &str) -> Result<Message, Error> {
+    let bytes = bs58::decode(message)
+        .into_vec()
+        .map_err(|e| Error::Generic(format!("invalid message encoding: {}", e)))?;
+    solana_sdk::program_utils::limited_deserialize(&bytes)
+        .map_err(|e| Error::Generic(format!("invalid message: {}", e)))
+}
+
+/// Read one line, `None` once the peer has closed the connection.
+pub async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<String>, Error> {
+    let mut line = String::new();
+    let read = (&mut *reader)
+        .take(MAX_LINE_LEN as u64 + 1)
+        .read_line(&mut line)
+        .await?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with('\n') {
+        return Err(Error::Generic(if line.len() > MAX_LINE_LEN {
+            "line too long".to_string()
+        } else {
+            "connection closed mid-line".to_string()
+        }));
+    }
+    Ok(Some(line))
+}
+
+pub async fn write_line<W: AsyncWrite + Unpin, T: Serialize>(
+    writer: &mut W,
+    value: &T,
+) -> Result<(), Error> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey::Pubkey;
+    use solana_system_interface::instruction as system_instruction;
+
+    use super::*;
+
+    #[test]
+    fn test_serialize_request() {
+        assert_eq!(
+            serde_json::to_value(Request::Pubkey {
+                twitter_id: 1,
+                wallet_index: 0
+            })
+            .unwrap(),
+            serde_json::json!({ "method": "pubkey", "params": { "twitterId": 1 } })
+        );
+        assert_eq!(
+            serde_json::to_value(Request::Pubkey {
+                twitter_id: 1,
+                wallet_index: 2
+            })
+            .unwrap(),
+            serde_json::json!({ "method": "pubkey", "params": { "twitterId": 1, "walletIndex": 2 } })
+        );
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let payer = Pubkey::new_unique();
+        let message = Message::new(
+            &[system_instruction::transfer(
+                &payer,
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(&payer),
+        );
+
+        let Request::SignMessage {
+            message: encoded, ..
+        } = Request::sign_message(1, 0, &message)
+        else {
+            panic!("expected a sign request");
+        };
+        assert_eq!(decode_message(&encoded).unwrap(), message);
+        assert!(decode_message("not base58!").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_line() {
+        let mut input: &[u8] = b"{}\n{\"a\"";
+        assert_eq!(read_line(&mut input).await.unwrap().unwrap(), "{}\n");
+        assert!(read_line(&mut input).await.is_err());
+        assert_eq!(read_line(&mut input).await.unwrap(), None);
+
+        let long = vec![b'a'; MAX_LINE_LEN + 2];
+        assert!(read_line(&mut long.as_slice()).await.is_err());
+    }
+}
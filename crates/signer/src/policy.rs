@@ -0,0 +1,502 @@
+use std::collections::BTreeSet;
+use std::str::FromStr as _;
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::{instruction::CompiledInstruction, message::Message, pubkey, pubkey::Pubkey};
+
+use crate::error::Error;
+
+const TOKEN_PROGRAM: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const TOKEN_2022_PROGRAM: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+const ASSOCIATED_TOKEN_PROGRAM: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+const JUPITER_V6_PROGRAM: Pubkey = pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+const TRENCH_PROGRAM: Pubkey = pubkey!("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
+const SYSTEM_PROGRAM: Pubkey = pubkey!("11111111111111111111111111111111");
+const NATIVE_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+
+/// System program `Transfer`, a bincode u32 tag followed by the u64 lamports
+const SYSTEM_TRANSFER: [u8; 4] = 2u32.to_le_bytes();
+const SYSTEM_TRANSFER_LEN: usize = 12;
+/// SPL Token instruction tags
+const TOKEN_CLOSE_ACCOUNT: u8 = 9;
+const TOKEN_SYNC_NATIVE: u8 = 17;
+/// Associated token account instruction tags, no data at all is `Create` too
+const ATA_CREATE: u8 = 0;
+const ATA_CREATE_IDEMPOTENT: u8 = 1;
+
+/// Where a Jupiter v6 swap instruction pays out: positions of its
+/// destination token account and destination mint, and of the user's token
+/// account paid instead when the destination is left out
+struct Route {
+    destination: usize,
+    destination_mint: usize,
+    user_destination: Option<usize>,
+}
+
+/// Jupiter v6 swap instructions by Anchor discriminator
+const JUPITER_ROUTES: [([u8; 8], Route); 6] = [
+    // route
+    (
+        [229, 23, 203, 151, 122, 227, 173, 42],
+        Route {
+            destination: 4,
+            destination_mint: 5,
+            user_destination: Some(3),
+        },
+    ),
+    // route_with_token_ledger
+    (
+        [150, 86, 71, 116, 167, 93, 14, 104],
+        Route {
+            destination: 4,
+            destination_mint: 5,
+            user_destination: Some(3),
+        },
+    ),
+    // exact_out_route
+    (
+        [208, 51, 239, 151, 123, 43, 237, 92],
+        Route {
+            destination: 4,
+            destination_mint: 6,
+            user_destination: Some(3),
+        },
+    ),
+    // shared_accounts_route
+    (
+        [193, 32, 155, 51, 65, 214, 156, 129],
+        Route {
+            destination: 6,
+            destination_mint: 8,
+            user_destination: None,
+        },
+    ),
+    // shared_accounts_route_with_token_ledger
+    (
+        [230, 121, 143, 80, 119, 159, 106, 170],
+        Route {
+            destination: 6,
+            destination_mint: 8,
+            user_destination: None,
+        },
+    ),
+    // shared_accounts_exact_out_route
+    (
+        [176, 209, 105, 168, 154, 125, 69, 62],
+        Route {
+            destination: 6,
+            destination_mint: 8,
+            user_destination: None,
+        },
+    ),
+];
+
+/// Trench instructions by Anchor discriminator
+const TRENCH_CREATE: [u8; 8] = [24, 30, 200, 40, 5, 28, 7, 119];
+const TRENCH_BUY: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+
+/// Which transactions the signer agrees to sign.
+///
+/// A transaction is signed only if the wallet pays its fees and every
+/// instruction either calls an allowed program or is one of the System, SPL
+/// Token and associated token account instructions the trades need, see
+/// [`is_wallet_upkeep`]. Any other instruction of those programs, such as a
+/// transfer draining the wallet, is refused.
+///
+/// Jupiter v6 and trench are only trusted with the trades the wallet makes,
+/// see [`is_wallet_trade`]: a swap must pay out to one of the wallet's token
+/// accounts, and a launch or buy must be the wallet's own. Their other
+/// instructions are refused even when allowed. Any other allowed program is
+/// trusted completely, every instruction calling it is signed.
+///
+/// Only legacy [`Message`]s are accepted, versioned messages and their address
+/// lookup tables never reach the policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Policy {
+    pub allowed_programs: BTreeSet<Pubkey>,
+}
+
+/// On-disk form of a [`Policy`], addresses in base58.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PolicyFile {
+    allowed_programs: Vec<String>,
+}
+
+impl Default for Policy {
+    /// The programs Jupiter swaps and trench launches call, besides the
+    /// System, SPL Token and associated token account instructions they need.
+    fn default() -> Self {
+        Self {
+            allowed_programs: BTreeSet::from([
+                solana_sdk::compute_budget::ID,
+                JUPITER_V6_PROGRAM,
+                TRENCH_PROGRAM,
+            ]),
+        }
+    }
+}
+
+impl Policy {
+    /// Load a policy from a JSON file like `{ "allowedPrograms": ["<base58>", ..] }`.
+    pub fn load(path: &str) -> Result<Self, Error> {
+        let file: PolicyFile = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let allowed_programs = file
+            .allowed_programs
+            .iter()
+            .map(|program| {
+                Pubkey::from_str(program)
+                    .map_err(|e| Error::Generic(format!("invalid program {}: {}", program, e)))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { allowed_programs })
+    }
+
+    pub fn check(&self, wallet: &Pubkey, message: &Message) -> Result<(), Error> {
+        if message.account_keys.first() != Some(wallet) {
+            return Err(Error::Policy(
+                "the wallet must be the fee payer".to_string(),
+            ));
+        }
+        for instruction in &message.instructions {
+            let program = message
+                .account_keys
+                .get(instruction.program_id_index as usize)
+                .ok_or_else(|| Error::Policy("program index out of bounds".to_string()))?;
+            let allowed = if *program == JUPITER_V6_PROGRAM || *program == TRENCH_PROGRAM {
+                self.allowed_programs.contains(program)
+                    && is_wallet_trade(wallet, program, instruction, message)
+            } else {
+                self.allowed_programs.contains(program)
+                    || is_wallet_upkeep(wallet, program, instruction, message)
+            };
+            if !allowed {
+                return Err(Error::Policy(format!(
+                    "instruction of program {} is not allowed",
+                    program
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The wallet's wrapped SOL account, the only one SOL may be transferred to.
+pub fn wrapped_sol_account(wallet: &Pubkey) -> Pubkey {
+    token_account(wallet, &NATIVE_MINT, &TOKEN_PROGRAM)
+}
+
+/// The wallet's associated token account for `mint`.
+fn token_account(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM,
+    )
+    .0
+}
+
+/// Whether `instruction` is a Jupiter swap paying out to the wallet's own
+/// token account, or a trench launch or buy made by and for the wallet.
+fn is_wallet_trade(
+    wallet: &Pubkey,
+    program: &Pubkey,
+    instruction: &CompiledInstruction,
+    message: &Message,
+) -> bool {
+    let account = |position: usize| {
+        instruction
+            .accounts
+            .get(position)
+            .and_then(|index| message.account_keys.get(*index as usize))
+    };
+    let owns =
+        |token_account_key: Option<&Pubkey>, mint: Option<&Pubkey>| match (token_account_key, mint)
+        {
+            (Some(key), Some(mint)) => [TOKEN_PROGRAM, TOKEN_2022_PROGRAM]
+                .iter()
+                .any(|token_program| *key == token_account(wallet, mint, token_program)),
+            _ => false,
+        };
+    let discriminator = instruction.data.get(..8);
+
+    if *program == JUPITER_V6_PROGRAM {
+        let Some((_, route)) = JUPITER_ROUTES
+            .iter()
+            .find(|(route, _)| discriminator == Some(route.as_slice()))
+        else {
+            return false;
+        };
+        // Anchor passes the program itself for a left out optional account
+        let destination = match (account(route.destination), route.user_destination) {
+            (Some(destination), Some(user_destination)) if *destination == JUPITER_V6_PROGRAM => {
+                account(user_destination)
+            }
+            (destination, _) => destination,
+        };
+        owns(destination, account(route.destination_mint))
+    } else if discriminator == Some(TRENCH_CREATE.as_slice()) {
+        // The wallet pays and is the creator collecting the fees
+        account(7) == Some(wallet) && instruction.data.ends_with(wallet.as_ref())
+    } else if discriminator == Some(TRENCH_BUY.as_slice()) {
+        account(6) == Some(wallet) && owns(account(5), account(2))
+    } else {
+        false
+    }
+}
+
+/// Whether `instruction` is the wallet creating its own token account, or
+/// wrapping or unwrapping its own SOL.
+fn is_wallet_upkeep(
+    wallet: &Pubkey,
+    program: &Pubkey,
+    instruction: &CompiledInstruction,
+    message: &Message,
+) -> bool {
+    let account = |position: usize| {
+        instruction
+            .accounts
+            .get(position)
+            .and_then(|index| message.account_keys.get(*index as usize))
+    };
+
+    if *program == SYSTEM_PROGRAM {
+        instruction.data.len() == SYSTEM_TRANSFER_LEN
+            && instruction.data.starts_with(&SYSTEM_TRANSFER)
+            && account(0) == Some(wallet)
+            && account(1) == Some(&wrapped_sol_account(wallet))
+    } else if *program == TOKEN_PROGRAM {
+        match instruction.data.as_slice() {
+            // Only updates the balance of a wrapped SOL account
+            [TOKEN_SYNC_NATIVE] => true,
+            // The wallet's own account, with the rent and any wrapped SOL back to it
+            [TOKEN_CLOSE_ACCOUNT] => account(1) == Some(wallet) && account(2) == Some(wallet),
+            _ => false,
+        }
+    } else if *program == ASSOCIATED_TOKEN_PROGRAM {
+        // Paid for and owned by the wallet
+        matches!(
+            instruction.data.as_slice(),
+            [] | [ATA_CREATE] | [ATA_CREATE_IDEMPOTENT]
+        ) && account(0) == Some(wallet)
+            && account(2) == Some(wallet)
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_system_interface::instruction as system_instruction;
+
+    use super::*;
+
+    fn token_instruction(data: u8, accounts: &[Pubkey]) -> Instruction {
+        Instruction::new_with_bytes(
+            TOKEN_PROGRAM,
+            &[data],
+            accounts
+                .iter()
+                .map(|account| AccountMeta::new(*account, false))
+                .collect(),
+        )
+    }
+
+    fn create_token_account(funder: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            ASSOCIATED_TOKEN_PROGRAM,
+            &[ATA_CREATE_IDEMPOTENT],
+            vec![
+                AccountMeta::new(*funder, true),
+                AccountMeta::new(Pubkey::new_unique(), false),
+                AccountMeta::new_readonly(*owner, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM, false),
+                AccountMeta::new_readonly(TOKEN_PROGRAM, false),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_check() {
+        let policy = Policy::default();
+        let wallet = Pubkey::new_unique();
+        let transfer = system_instruction::transfer(&wallet, &wrapped_sol_account(&wallet), 1);
+
+        let message = Message::new(std::slice::from_ref(&transfer), Some(&wallet));
+        assert!(policy.check(&wallet, &message).is_ok());
+
+        // Someone else pays
+        let payer = Pubkey::new_unique();
+        let message = Message::new(std::slice::from_ref(&transfer), Some(&payer));
+        assert!(policy.check(&wallet, &message).is_err());
+
+        // Unknown program
+        let unknown = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        let message = Message::new(&[transfer, unknown], Some(&wallet));
+        assert!(policy.check(&wallet, &message).is_err());
+    }
+
+    #[test]
+    fn test_check_swap_upkeep() {
+        let policy = Policy::default();
+        let wallet = Pubkey::new_unique();
+        let wrapped_sol = wrapped_sol_account(&wallet);
+
+        // Jupiter's setup and cleanup around a swap from SOL
+        let message = Message::new(
+            &[
+                create_token_account(&wallet, &wallet, &NATIVE_MINT),
+                system_instruction::transfer(&wallet, &wrapped_sol, 1_000_000),
+                token_instruction(TOKEN_SYNC_NATIVE, &[wrapped_sol]),
+                token_instruction(TOKEN_CLOSE_ACCOUNT, &[wrapped_sol, wallet, wallet]),
+            ],
+            Some(&wallet),
+        );
+        assert!(policy.check(&wallet, &message).is_ok());
+
+        let thief = Pubkey::new_unique();
+        for instruction in [
+            // A bare drain transfer
+            system_instruction::transfer(&wallet, &thief, 1_000_000),
+            system_instruction::assign(&wallet, &thief),
+            // Unwrapping into someone else's wallet
+            token_instruction(TOKEN_CLOSE_ACCOUNT, &[wrapped_sol, thief, wallet]),
+            // Token transfers only happen inside the swap
+            token_instruction(12, &[wrapped_sol, NATIVE_MINT, thief, wallet]),
+            // Paying the rent of someone else's token account
+            create_token_account(&wallet, &thief, &NATIVE_MINT),
+        ] {
+            let message = Message::new(&[instruction], Some(&wallet));
+            assert!(matches!(
+                policy.check(&wallet, &message),
+                Err(Error::Policy(_))
+            ));
+        }
+
+        // Unless the policy allows the whole program
+        let policy = Policy {
+            allowed_programs: BTreeSet::from([SYSTEM_PROGRAM]),
+        };
+        let message = Message::new(
+            &[system_instruction::transfer(&wallet, &thief, 1)],
+            Some(&wallet),
+        );
+        assert!(policy.check(&wallet, &message).is_ok());
+    }
+
+    fn program_instruction(
+        program: Pubkey,
+        discriminator: [u8; 8],
+        accounts: &[Pubkey],
+    ) -> Instruction {
+        Instruction::new_with_bytes(
+            program,
+            &discriminator,
+            accounts
+                .iter()
+                .map(|account| AccountMeta::new(*account, false))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_check_jupiter_destination() {
+        let policy = Policy::default();
+        let wallet = Pubkey::new_unique();
+        let thief = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let check = |instruction: Instruction| {
+            policy.check(&wallet, &Message::new(&[instruction], Some(&wallet)))
+        };
+
+        // shared_accounts_route: destination at 6, destination mint at 8
+        let shared_accounts_route = |destination: Pubkey| {
+            let mut accounts = vec![Pubkey::new_unique(); 9];
+            accounts[2] = wallet;
+            accounts[6] = destination;
+            accounts[8] = mint;
+            program_instruction(
+                JUPITER_V6_PROGRAM,
+                [193, 32, 155, 51, 65, 214, 156, 129],
+                &accounts,
+            )
+        };
+        assert!(check(shared_accounts_route(token_account(
+            &wallet,
+            &mint,
+            &TOKEN_PROGRAM
+        )))
+        .is_ok());
+        assert!(check(shared_accounts_route(token_account(
+            &wallet,
+            &mint,
+            &TOKEN_2022_PROGRAM
+        )))
+        .is_ok());
+        assert!(matches!(
+            check(shared_accounts_route(token_account(
+                &thief,
+                &mint,
+                &TOKEN_PROGRAM
+            ))),
+            Err(Error::Policy(_))
+        ));
+
+        // route: without a destination the user's token account at 3 is paid
+        let route = |user_destination: Pubkey, destination: Pubkey| {
+            let mut accounts = vec![Pubkey::new_unique(); 6];
+            accounts[1] = wallet;
+            accounts[3] = user_destination;
+            accounts[4] = destination;
+            accounts[5] = mint;
+            program_instruction(
+                JUPITER_V6_PROGRAM,
+                [229, 23, 203, 151, 122, 227, 173, 42],
+                &accounts,
+            )
+        };
+        let own = token_account(&wallet, &mint, &TOKEN_PROGRAM);
+        let foreign = token_account(&thief, &mint, &TOKEN_PROGRAM);
+        assert!(check(route(own, JUPITER_V6_PROGRAM)).is_ok());
+        assert!(check(route(foreign, JUPITER_V6_PROGRAM)).is_err());
+        assert!(check(route(own, foreign)).is_err());
+
+        // Any other Jupiter instruction
+        assert!(check(program_instruction(JUPITER_V6_PROGRAM, [0; 8], &[wallet])).is_err());
+    }
+
+    #[test]
+    fn test_check_trench_buyer() {
+        let policy = Policy::default();
+        let wallet = Pubkey::new_unique();
+        let thief = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let buy = |user_token_account: Pubkey| {
+            let mut accounts = vec![Pubkey::new_unique(); 7];
+            accounts[2] = mint;
+            accounts[5] = user_token_account;
+            accounts[6] = wallet;
+            let message = Message::new(
+                &[program_instruction(TRENCH_PROGRAM, TRENCH_BUY, &accounts)],
+                Some(&wallet),
+            );
+            policy.check(&wallet, &message)
+        };
+        assert!(buy(token_account(&wallet, &mint, &TOKEN_PROGRAM)).is_ok());
+        assert!(matches!(
+            buy(token_account(&thief, &mint, &TOKEN_PROGRAM)),
+            Err(Error::Policy(_))
+        ));
+
+        let create = |creator: Pubkey| {
+            let mut accounts = vec![Pubkey::new_unique(); 8];
+            accounts[7] = wallet;
+            let mut instruction = program_instruction(TRENCH_PROGRAM, TRENCH_CREATE, &accounts);
+            instruction.data.extend_from_slice(creator.as_ref());
+            policy.check(&wallet, &Message::new(&[instruction], Some(&wallet)))
+        };
+        assert!(create(wallet).is_ok());
+        assert!(create(thief).is_err());
+    }
+}
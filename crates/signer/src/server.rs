@@ -0,0 +1,315 @@
+use std::os::unix::fs::{FileTypeExt as _, MetadataExt as _, PermissionsExt as _};
+use std::sync::Arc;
+
+use solana_sdk::{signature::Keypair, signer::Signer as _};
+use tokio::io::BufReader;
+use tokio::net::{UnixListener, UnixStream};
+use x_link_wallet::{exports::Exports, keygen::KeyGen, scheme::DerivationScheme};
+
+use crate::{
+    error::Error,
+    message::{decode_message, read_line, write_line, Request, Response},
+    policy::Policy,
+};
+
+/// Owns the seed and answers derivation and signing requests on a Unix socket.
+pub struct Server {
+    keygen: KeyGen,
+    policy: Policy,
+    exports: Exports,
+}
+
+impl Server {
+    pub fn new(keygen: KeyGen, policy: Policy) -> Self {
+        Self {
+            keygen,
+            policy,
+            exports: Exports::default(),
+        }
+    }
+
+    /// Warn whenever a key listed in `exports` is used.
+    pub fn with_exports(mut self, exports: Exports) -> Self {
+        self.exports = exports;
+        self
+    }
+
+    /// Listen on `socket` until an error occurs. The socket is only accessible
+    /// to the user running the signer.
+    pub async fn run(self, socket: &str) -> Result<(), Error> {
+        let listener = Self::bind(socket)?;
+        let owner = std::fs::metadata(socket)?.uid();
+        let server = Arc::new(self);
+        tracing::info!(socket, "signer listening");
+
+        loop {
+            let (stream, _) = listener.accept().await?;
+            match stream.peer_cred() {
+                Ok(cred) if cred.uid() == owner => {}
+                Ok(cred) => {
+                    tracing::warn!(uid = cred.uid(), "rejected connection from another user");
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!("rejected connection without credentials: {:?}", e);
+                    continue;
+                }
+            }
+
+            let server = server.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.serve(stream).await {
+                    tracing::error!("error serving connection: {:?}", e);
+                }
+            });
+        }
+    }
+
+    /// Answer a single request, refusals are returned as [`Response::Error`].
+    pub fn handle(&self, request: Request) -> Response {
+        self.handle_inner(request).unwrap_or_else(|e| {
+            tracing::warn!("request refused: {}", e);
+            Response::Error {
+                message: e.to_string(),
+            }
+        })
+    }
+
+    async fn serve(&self, stream: UnixStream) -> Result<(), Error> {
+        let (read, mut write) = stream.into_split();
+        let mut reader = BufReader::new(read);
+        while let Some(line) = read_line(&mut reader).await? {
+            let response = match serde_json::from_str(&line) {
+                Ok(request) => self.handle(request),
+                Err(e) => Response::Error {
+                    message: format!("invalid request: {}", e),
+                },
+            };
+            write_line(&mut write, &response).await?;
+        }
+        Ok(())
+    }
+
+    fn handle_inner(&self, request: Request) -> Result<Response, Error> {
+        match request {
+            Request::Pubkey {
+                twitter_id,
+                wallet_index,
+            } => {
+                let (scheme, keypair) = self.derive(twitter_id, wallet_index)?;
+                Ok(Response::Pubkey {
+                    pubkey: keypair.pubkey().to_string(),
+                    scheme: scheme.version(),
+                    exported_at: self.exported_at(twitter_id, wallet_index),
+                })
+            }
+            Request::EvmAddress {
+                twitter_id,
+                wallet_index,
+            } => {
+                let address = self
+                    .keygen
+                    .derive_evm(twitter_id, wallet_index)
+                    .map_err(|e| Error::Generic(format!("error generating key: {}", e)))?;
+                Ok(Response::EvmAddress {
+                    address: address.to_string(),
+                })
+            }
+            Request::SignMessage {
+                twitter_id,
+                wallet_index,
+                message,
+            } => {
+                let message = decode_message(&message)?;
+                let (_, keypair) = self.derive(twitter_id, wallet_index)?;
+                self.policy.check(&keypair.pubkey(), &message)?;
+
+                let signature = keypair.sign_message(&message.serialize());
+                tracing::info!(twitter_id, wallet_index, wallet = %keypair.pubkey(), %signature, "signed message");
+                Ok(Response::Signature {
+                    signature: signature.to_string(),
+                })
+            }
+        }
+    }
+
+    /// When the wallet's key was exported, only main wallets can be.
+    fn exported_at(&self, twitter_id: u64, wallet_index: u32) -> Option<u64> {
+        match wallet_index {
+            0 => self
+                .exports
+                .get(twitter_id)
+                .map(|record| record.exported_at),
+            _ => None,
+        }
+    }
+
+    fn derive(
+        &self,
+        twitter_id: u64,
+        wallet_index: u32,
+    ) -> Result<(DerivationScheme, Keypair), Error> {
+        // Only main wallets can be exported
+        if wallet_index == 0 {
+            self.exports.warn_if_exported(twitter_id);
+        }
+        self.keygen
+            .derive_indexed(twitter_id, wallet_index)
+            .map_err(|e| Error::Generic(format!("error generating key: {}", e)))
+    }
+
+    /// Bind the socket, replacing one left behind by a previous run but never
+    /// a live socket or any other kind of file.
+    fn bind(socket: &str) -> Result<UnixListener, Error> {
+        match std::fs::symlink_metadata(socket) {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                if std::os::unix::net::UnixStream::connect(socket).is_ok() {
+                    return Err(Error::Generic(format!(
+                        "another signer is listening on {}",
+                        socket
+                    )));
+                }
+                std::fs::remove_file(socket)?;
+            }
+            Ok(_) => {
+                return Err(Error::Generic(format!(
+                    "{} exists and is not a socket",
+                    socket
+                )))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let listener = UnixListener::bind(socket)?;
+        std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))?;
+        Ok(listener)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{message::Message, pubkey::Pubkey, signature::Signature};
+    use solana_system_interface::instruction as system_instruction;
+
+    use x_link_wallet::exports::ExportRecord;
+
+    use super::*;
+    use crate::policy::wrapped_sol_account;
+
+    const SECRET: &[u8; 64] = b"What the fuck did you just fucking say about me you little bitch";
+    const ID: u64 = 1722992406616756224;
+    const WALLET: &str = "ENuzcbEgZq9j9BQCSgsFfvnMeX8aY7xGDizGyf29eByN";
+
+    fn server() -> Server {
+        Server::new(KeyGen::from(*SECRET), Policy::default())
+    }
+
+    #[test]
+    fn test_pubkey() {
+        assert_eq!(
+            server().handle(Request::Pubkey {
+                twitter_id: ID,
+                wallet_index: 0
+            }),
+            Response::Pubkey {
+                pubkey: WALLET.to_string(),
+                scheme: 1,
+                exported_at: None,
+            }
+        );
+        assert_eq!(
+            server().handle(Request::Pubkey {
+                twitter_id: ID,
+                wallet_index: 1
+            }),
+            Response::Pubkey {
+                pubkey: "Fow8L5TNh1tcRh9FqW1iANgBJGBqKWYJADZXPRKTXRZy".to_string(),
+                scheme: 2,
+                exported_at: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_exported_pubkey() {
+        let secret_file = std::env::temp_dir()
+            .join(format!("x-link-signer-exports-{}", std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        let server = server().with_exports(Exports::load([secret_file.as_str()]).unwrap());
+        let exported_at = |wallet_index| match server.handle(Request::Pubkey {
+            twitter_id: ID,
+            wallet_index,
+        }) {
+            Response::Pubkey { exported_at, .. } => exported_at,
+            response => panic!("unexpected response: {:?}", response),
+        };
+        assert_eq!(exported_at(0), None);
+
+        // Exported while the signer runs
+        Exports::append(
+            &secret_file,
+            &ExportRecord {
+                twitter_id: ID,
+                pubkey: WALLET.to_string(),
+                scheme: 1,
+                format: "json".to_string(),
+                exported_at: 1_700_000_000,
+            },
+        )
+        .unwrap();
+        assert_eq!(exported_at(0), Some(1_700_000_000));
+        // Sub-wallets have keys of their own
+        assert_eq!(exported_at(1), None);
+
+        std::fs::remove_file(Exports::path_for(&secret_file)).unwrap();
+    }
+
+    #[test]
+    fn test_evm_address() {
+        assert_eq!(
+            server().handle(Request::EvmAddress {
+                twitter_id: ID,
+                wallet_index: 0
+            }),
+            Response::EvmAddress {
+                address: "0xed1B0701536Db26BB2BC2059C9C2ada1aD21B235".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_sign_message() {
+        let wallet = WALLET.parse::<Pubkey>().unwrap();
+        // Wrapping SOL, as before a swap
+        let message = Message::new(
+            &[system_instruction::transfer(
+                &wallet,
+                &wrapped_sol_account(&wallet),
+                1,
+            )],
+            Some(&wallet),
+        );
+
+        let Response::Signature { signature } =
+            server().handle(Request::sign_message(ID, 0, &message))
+        else {
+            panic!("expected a signature");
+        };
+        let signature = signature.parse::<Signature>().unwrap();
+        assert!(signature.verify(wallet.as_ref(), &message.serialize()));
+
+        // Another twitter ID's wallet isn't the fee payer
+        assert!(matches!(
+            server().handle(Request::sign_message(ID + 1, 0, &message)),
+            Response::Error { .. }
+        ));
+        // Nor is one of its sub-wallets
+        assert!(matches!(
+            server().handle(Request::sign_message(ID, 1, &message)),
+            Response::Error { .. }
+        ));
+    }
+}
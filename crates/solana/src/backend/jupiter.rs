@@ -1,6 +1,7 @@
 use crate::{
     constants::{DEFAULT_SLIPPAGE_BPS, JUP_BASE_PATH},
     error::Error,
+    signer::WalletSigner,
 };
 use jupiter_swap_api_client::{
     quote::{QuoteRequest, QuoteResponse},
@@ -8,8 +9,7 @@ use jupiter_swap_api_client::{
     transaction_config::TransactionConfig,
     JupiterSwapApiClient,
 };
-use solana_sdk::{hash::Hash, pubkey::Pubkey, signer::Signer, transaction::Transaction};
-use x_link_types::account::Account;
+use solana_sdk::{hash::Hash, pubkey::Pubkey, transaction::Transaction};
 
 pub struct Backend {
     client: JupiterSwapApiClient,
@@ -48,19 +48,19 @@ impl Backend {
             slippage_bps: DEFAULT_SLIPPAGE_BPS,
             ..Default::default()
         };
-        self.client.quote(&request).await.map_err(Error::from)
+        self.client.quote(&request).await.map_err(Error::from_quote)
     }
 
     pub(crate) async fn instructions(
         &self,
-        account: &Account,
+        wallet: &impl WalletSigner,
         input_mint: &Pubkey,
         output_mint: &Pubkey,
         amount: u64,
     ) -> Result<SwapInstructionsResponse, Error> {
         let quote = self.quote(*input_mint, *output_mint, amount).await?;
         let request = SwapRequest {
-            user_public_key: account.pubkey(),
+            user_public_key: wallet.pubkey(),
             quote_response: quote,
             config: self.transaction_config(),
         };
@@ -72,14 +72,14 @@ impl Backend {
 
     pub(crate) async fn swap_transaction(
         &self,
-        account: &Account,
+        wallet: &impl WalletSigner,
         input_mint: &Pubkey,
         output_mint: &Pubkey,
         amount: u64,
         recent_blockhash: Hash,
     ) -> Result<Transaction, Error> {
         let instructions = self
-            .instructions(account, input_mint, output_mint, amount)
+            .instructions(wallet, input_mint, output_mint, amount)
             .await?;
         let mut ixs = vec![];
         ixs.extend(instructions.compute_budget_instructions);
@@ -88,12 +88,9 @@ impl Backend {
         if let Some(ix) = instructions.cleanup_instruction {
             ixs.push(ix)
         }
-        let tx = Transaction::new_signed_with_payer(
-            &ixs,
-            Some(&account.pubkey()),
-            &[account],
-            recent_blockhash,
-        );
+        let mut tx = Transaction::new_with_payer(&ixs, Some(&wallet.pubkey()));
+        tx.message.recent_blockhash = recent_blockhash;
+        wallet.sign_transaction(&mut tx).await?;
         Ok(tx)
     }
 }
@@ -103,12 +100,16 @@ mod tests {
     use super::*;
     use crate::constants::{NATIVE_MINT, USDC_MINT};
     use solana_sdk::signature::Keypair;
-    use x_link_types::account::Account;
+    use x_link_types::{account::Account, social::SocialId, twitter::TwitterId};
 
     // Helper function to create test account
     fn create_test_account() -> Account {
         let keypair = Keypair::new();
-        Account::new(123456, keypair)
+        Account::new(
+            SocialId::Twitter(TwitterId::new(123456).unwrap()),
+            keypair,
+            1,
+        )
     }
 
     #[tokio::test]
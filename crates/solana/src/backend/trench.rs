@@ -1,8 +1,364 @@
+//! Token launches on the trench program, the pump.fun bonding curve new tokens
+//! trade on until they graduate. Instructions are built by hand from its
+//! Anchor layout, like [`crate::token`]'s.
+
+use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer as _,
+    transaction::Transaction,
+};
+
+use crate::{
+    constants::{
+        ASSOCIATED_TOKEN_PROGRAM_ID, SYSTEM_PROGRAM_ID, TOKEN_METADATA_PROGRAM_ID,
+        TOKEN_PROGRAM_ID, TRENCH_FEE_BPS, TRENCH_FEE_PROGRAM_ID, TRENCH_FEE_RECIPIENT,
+        TRENCH_PROGRAM_ID,
+    },
+    error::Error,
+    signer::WalletSigner,
+    token::{associated_token_address, create_associated_token_account_idempotent},
+};
+
+const CREATE: [u8; 8] = [24, 30, 200, 40, 5, 28, 7, 119];
+const BUY: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+
+/// Reserves every bonding curve starts with, tokens have 6 decimals
+const INITIAL_VIRTUAL_SOL_RESERVES: u128 = 30_000_000_000;
+const INITIAL_VIRTUAL_TOKEN_RESERVES: u128 = 1_073_000_000_000_000;
+const INITIAL_REAL_TOKEN_RESERVES: u128 = 793_100_000_000_000;
+
+/// Limits of the Metaplex metadata account, in bytes
+const MAX_NAME_LENGTH: usize = 32;
+const MAX_SYMBOL_LENGTH: usize = 10;
+const MAX_URI_LENGTH: usize = 200;
+
+/// What a token is launched with. Anything else, such as its description or
+/// image, goes in the JSON document at `uri`.
+#[derive(Debug, Clone)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+impl TokenMetadata {
+    fn validate(&self) -> Result<(), Error> {
+        for (field, value, max) in [
+            ("name", &self.name, MAX_NAME_LENGTH),
+            ("symbol", &self.symbol, MAX_SYMBOL_LENGTH),
+            ("uri", &self.uri, MAX_URI_LENGTH),
+        ] {
+            if value.is_empty() || value.len() > max {
+                return Err(Error::InvalidMetadata(format!(
+                    "{} must be 1 to {} bytes, got {}",
+                    field,
+                    max,
+                    value.len()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A launched token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Launch {
+    pub mint: Pubkey,
+    pub signature: Signature,
+}
+
 pub struct Backend {}
 
-// TODO: Implement the Backend struct
+impl Default for Backend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Backend {
     pub fn new() -> Self {
         Self {}
     }
+
+    /// A transaction creating a new mint with `token`'s metadata, then buying
+    /// it with `amount` lamports if any. Returns the mint with the transaction,
+    /// which the mint has signed and `wallet` has paid for and signed.
+    pub(crate) async fn create_transaction(
+        &self,
+        wallet: &impl WalletSigner,
+        token: &TokenMetadata,
+        amount: u64,
+        recent_blockhash: Hash,
+    ) -> Result<(Pubkey, Transaction), Error> {
+        token.validate()?;
+        let mint = Keypair::new();
+        let user = wallet.pubkey();
+
+        let mut ixs = vec![create(&mint.pubkey(), &user, token)];
+        if amount > 0 {
+            ixs.push(create_associated_token_account_idempotent(
+                &user,
+                &user,
+                &mint.pubkey(),
+                &TOKEN_PROGRAM_ID,
+            ));
+            ixs.push(buy(
+                &mint.pubkey(),
+                &user,
+                &user,
+                initial_buy_tokens(amount),
+                amount,
+            ));
+        }
+
+        let mut tx = Transaction::new_with_payer(&ixs, Some(&user));
+        tx.try_partial_sign(&[&mint], recent_blockhash)?;
+        wallet.sign_transaction(&mut tx).await?;
+        Ok((mint.pubkey(), tx))
+    }
+}
+
+fn pda(seeds: &[&[u8]], program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(seeds, program).0
+}
+
+fn global() -> Pubkey {
+    pda(&[b"global"], &TRENCH_PROGRAM_ID)
+}
+
+fn event_authority() -> Pubkey {
+    pda(&[b"__event_authority"], &TRENCH_PROGRAM_ID)
+}
+
+pub fn bonding_curve(mint: &Pubkey) -> Pubkey {
+    pda(&[b"bonding-curve", mint.as_ref()], &TRENCH_PROGRAM_ID)
+}
+
+/// Tokens bought with `lamports` from a new curve, the fees held back.
+pub fn initial_buy_tokens(lamports: u64) -> u64 {
+    let sol = u128::from(lamports) * 10_000 / (10_000 + u128::from(TRENCH_FEE_BPS));
+    let k = INITIAL_VIRTUAL_SOL_RESERVES * INITIAL_VIRTUAL_TOKEN_RESERVES;
+    // Round the reserves left up so the curve never asks for more than `sol`
+    let reserves = k.div_ceil(INITIAL_VIRTUAL_SOL_RESERVES + sol);
+    let tokens = (INITIAL_VIRTUAL_TOKEN_RESERVES - reserves).min(INITIAL_REAL_TOKEN_RESERVES);
+    tokens as u64
+}
+
+fn push_string(data: &mut Vec<u8>, s: &str) {
+    data.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    data.extend_from_slice(s.as_bytes());
+}
+
+/// Create `mint` with its metadata and bonding curve, `user` paying and
+/// becoming its creator.
+pub fn create(mint: &Pubkey, user: &Pubkey, token: &TokenMetadata) -> Instruction {
+    let mut data = CREATE.to_vec();
+    push_string(&mut data, &token.name);
+    push_string(&mut data, &token.symbol);
+    push_string(&mut data, &token.uri);
+    data.extend_from_slice(user.as_ref());
+
+    let bonding_curve = bonding_curve(mint);
+    Instruction::new_with_bytes(
+        TRENCH_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(*mint, true),
+            AccountMeta::new_readonly(pda(&[b"mint-authority"], &TRENCH_PROGRAM_ID), false),
+            AccountMeta::new(bonding_curve, false),
+            AccountMeta::new(
+                associated_token_address(&bonding_curve, mint, &TOKEN_PROGRAM_ID),
+                false,
+            ),
+            AccountMeta::new_readonly(global(), false),
+            AccountMeta::new_readonly(TOKEN_METADATA_PROGRAM_ID, false),
+            AccountMeta::new(
+                pda(
+                    &[
+                        b"metadata",
+                        TOKEN_METADATA_PROGRAM_ID.as_ref(),
+                        mint.as_ref(),
+                    ],
+                    &TOKEN_METADATA_PROGRAM_ID,
+                ),
+                false,
+            ),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::rent::ID, false),
+            AccountMeta::new_readonly(event_authority(), false),
+            AccountMeta::new_readonly(TRENCH_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// Buy `amount` tokens of `mint` from its bonding curve for at most
+/// `max_sol_cost` lamports, fees included.
+pub fn buy(
+    mint: &Pubkey,
+    creator: &Pubkey,
+    user: &Pubkey,
+    amount: u64,
+    max_sol_cost: u64,
+) -> Instruction {
+    let mut data = BUY.to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&max_sol_cost.to_le_bytes());
+    // Don't track volume for rewards
+    data.push(0);
+
+    let bonding_curve = bonding_curve(mint);
+    Instruction::new_with_bytes(
+        TRENCH_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new_readonly(global(), false),
+            AccountMeta::new(TRENCH_FEE_RECIPIENT, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(bonding_curve, false),
+            AccountMeta::new(
+                associated_token_address(&bonding_curve, mint, &TOKEN_PROGRAM_ID),
+                false,
+            ),
+            AccountMeta::new(
+                associated_token_address(user, mint, &TOKEN_PROGRAM_ID),
+                false,
+            ),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new(
+                pda(&[b"creator-vault", creator.as_ref()], &TRENCH_PROGRAM_ID),
+                false,
+            ),
+            AccountMeta::new_readonly(event_authority(), false),
+            AccountMeta::new_readonly(TRENCH_PROGRAM_ID, false),
+            AccountMeta::new(
+                pda(&[b"global_volume_accumulator"], &TRENCH_PROGRAM_ID),
+                false,
+            ),
+            AccountMeta::new(
+                pda(
+                    &[b"user_volume_accumulator", user.as_ref()],
+                    &TRENCH_PROGRAM_ID,
+                ),
+                false,
+            ),
+            AccountMeta::new_readonly(
+                pda(
+                    &[b"fee_config", TRENCH_PROGRAM_ID.as_ref()],
+                    &TRENCH_FEE_PROGRAM_ID,
+                ),
+                false,
+            ),
+            AccountMeta::new_readonly(TRENCH_FEE_PROGRAM_ID, false),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey;
+    use x_link_types::{account::Account, social::SocialId, twitter::TwitterId};
+
+    use super::*;
+
+    fn token() -> TokenMetadata {
+        TokenMetadata {
+            name: "X-Link".to_string(),
+            symbol: "XLINK".to_string(),
+            uri: "https://example.com/xlink.json".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_addresses() {
+        assert_eq!(
+            global(),
+            pubkey!("4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf")
+        );
+        assert_eq!(
+            event_authority(),
+            pubkey!("Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1")
+        );
+        assert_eq!(
+            pda(&[b"mint-authority"], &TRENCH_PROGRAM_ID),
+            pubkey!("TSLvdd1pWpHVjahSpsvCXUbgwsL3JAcvokwaKt1eokM")
+        );
+    }
+
+    #[test]
+    fn test_initial_buy_tokens() {
+        assert_eq!(initial_buy_tokens(0), 0);
+        // 1 SOL, about 34.2M tokens after fees
+        let tokens = initial_buy_tokens(1_000_000_000);
+        assert!((34_100_000_000_000..34_300_000_000_000).contains(&tokens));
+        // Never more than the curve holds
+        assert_eq!(
+            initial_buy_tokens(u64::MAX),
+            INITIAL_REAL_TOKEN_RESERVES as u64
+        );
+    }
+
+    #[test]
+    fn test_create() {
+        let (mint, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = create(&mint, &user, &token());
+        assert_eq!(instruction.data[..8], CREATE);
+        assert_eq!(instruction.data[8..12], 6u32.to_le_bytes());
+        assert_eq!(&instruction.data[12..18], b"X-Link");
+        assert_eq!(
+            instruction.data[instruction.data.len() - 32..],
+            user.to_bytes()
+        );
+        assert!(instruction.accounts[0].is_signer);
+        assert!(instruction.accounts[7].is_signer);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(token().validate().is_ok());
+        let mut long = token();
+        long.symbol = "TOOLONGSYMBOL".to_string();
+        assert!(matches!(long.validate(), Err(Error::InvalidMetadata(_))));
+        let mut empty = token();
+        empty.name.clear();
+        assert!(empty.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_transaction() {
+        let account = Account::new(
+            SocialId::Twitter(TwitterId::new(123456).unwrap()),
+            Keypair::new(),
+            1,
+        );
+        let recent_blockhash = Hash::new_unique();
+
+        let (mint, transaction) = Backend::new()
+            .create_transaction(&account, &token(), 0, recent_blockhash)
+            .await
+            .unwrap();
+        assert_eq!(transaction.message.instructions.len(), 1);
+        assert_eq!(transaction.message.header.num_required_signatures, 2);
+        assert_eq!(
+            transaction.message.account_keys[0],
+            WalletSigner::pubkey(&account)
+        );
+        assert!(transaction.message.account_keys.contains(&mint));
+        assert!(transaction.verify().is_ok());
+
+        let (_, transaction) = Backend::new()
+            .create_transaction(&account, &token(), 100_000_000, recent_blockhash)
+            .await
+            .unwrap();
+        assert_eq!(transaction.message.instructions.len(), 3);
+        assert!(transaction.verify().is_ok());
+    }
 }
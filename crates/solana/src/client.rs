@@ -1,16 +1,19 @@
 use std::sync::Arc;
 
 use crate::{
-    backend::jupiter,
+    backend::{
+        jupiter,
+        trench::{self, Launch, TokenMetadata},
+    },
     constants::{HASH_EXPIRATION, NATIVE_MINT, SOL_BASE_PATH},
     error::Error,
     fresh_hash::FreshHash,
+    signer::WalletSigner,
 };
 use dashmap::DashMap;
 use jupiter_swap_api_client::quote::QuoteResponse;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{pubkey::Pubkey, signature::Signature, transaction::Transaction};
-use x_link_types::account::Account;
 
 use solana_sdk::hash::Hash;
 
@@ -49,6 +52,7 @@ impl CachedHash {
 
 pub struct Client {
     jup: jupiter::Backend,
+    trench: trench::Backend,
     sol: Arc<RpcClient>,
     trench_tokens: DashMap<Pubkey, bool>,
     fresh_hash: FreshHash,
@@ -58,6 +62,7 @@ impl Default for Client {
     fn default() -> Self {
         Self {
             jup: jupiter::Backend::new(),
+            trench: trench::Backend::new(),
             sol: Arc::new(RpcClient::new(SOL_BASE_PATH.to_string())),
             fresh_hash: FreshHash::new(
                 HASH_EXPIRATION,
@@ -82,15 +87,19 @@ impl Client {
     #[tracing::instrument(skip(self))]
     async fn send_transaction(&self, transaction: &Transaction) -> Result<Signature, Error> {
         tracing::debug!("Sending...");
-        let signature = self.sol.send_transaction(transaction).await?;
+        let signature = self
+            .sol
+            .send_transaction(transaction)
+            .await
+            .map_err(|e| Error::from_send(e, transaction))?;
         Ok(signature)
     }
 
     /// SELL `amount` of `mint` for native token
-    #[tracing::instrument(skip(self))]
+    #[tracing::instrument(skip(self, wallet), fields(wallet = %wallet.pubkey()))]
     pub async fn sell(
         &self,
-        account: &Account,
+        wallet: &impl WalletSigner,
         mint: &Pubkey,
         amount: u64,
     ) -> Result<Signature, Error> {
@@ -99,17 +108,17 @@ impl Client {
         self.send_transaction(
             &self
                 .jup
-                .swap_transaction(account, mint, &NATIVE_MINT, amount, recent_blockhash)
+                .swap_transaction(wallet, mint, &NATIVE_MINT, amount, recent_blockhash)
                 .await?,
         )
         .await
     }
 
     /// BUY `amount` of `mint` with native token
-    #[tracing::instrument(skip(self))]
+    #[tracing::instrument(skip(self, wallet), fields(wallet = %wallet.pubkey()))]
     pub async fn buy(
         &self,
-        account: &Account,
+        wallet: &impl WalletSigner,
         mint: &Pubkey,
         amount: u64,
     ) -> Result<Signature, Error> {
@@ -118,12 +127,32 @@ impl Client {
         self.send_transaction(
             &self
                 .jup
-                .swap_transaction(account, &NATIVE_MINT, mint, amount, recent_blockhash)
+                .swap_transaction(wallet, &NATIVE_MINT, mint, amount, recent_blockhash)
                 .await?,
         )
         .await
     }
 
+    /// Launch a new token on the trench, buying `amount` lamports of it for
+    /// `wallet` in the same transaction if not zero
+    #[tracing::instrument(skip(self, wallet), fields(wallet = %wallet.pubkey()))]
+    pub async fn create(
+        &self,
+        wallet: &impl WalletSigner,
+        token: &TokenMetadata,
+        amount: u64,
+    ) -> Result<Launch, Error> {
+        tracing::debug!("Creating...");
+        let recent_blockhash = self.recent_blockhash().await?;
+        let (mint, transaction) = self
+            .trench
+            .create_transaction(wallet, token, amount, recent_blockhash)
+            .await?;
+        let signature = self.send_transaction(&transaction).await?;
+        self.trench_tokens.insert(mint, true);
+        Ok(Launch { mint, signature })
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn quote(
         &self,
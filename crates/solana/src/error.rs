@@ -1,11 +1,180 @@
+use solana_sdk::{
+    instruction::InstructionError,
+    transaction::{Transaction, TransactionError},
+};
+
+use crate::constants::{
+    JUPITER_V6_PROGRAM_ID, SYSTEM_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID,
+    TRENCH_PROGRAM_ID,
+};
+
+/// `ResultWithNegativeLamports` of the system program and `InsufficientFunds`
+/// of both token programs
+const INSUFFICIENT_FUNDS: u32 = 1;
+/// `SlippageToleranceExceeded` of the Jupiter v6 program
+const JUPITER_SLIPPAGE_TOLERANCE_EXCEEDED: u32 = 6001;
+/// `TooMuchSolRequired` and `TooLittleSolReceived` of the trench program
+const TRENCH_SLIPPAGE: [u32; 2] = [6002, 6003];
+
+/// Markers in Jupiter's error responses for mints it can't route
+const UNROUTABLE: [&str; 4] = [
+    "TOKEN_NOT_TRADABLE",
+    "COULD_NOT_FIND_ANY_ROUTE",
+    "NO_ROUTES_FOUND",
+    "is not tradable",
+];
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("RPC error: {0}")]
     Generic(String),
 
+    #[error("Insufficient balance: {0}")]
+    InsufficientBalance(String),
+
+    #[error("Slippage tolerance exceeded")]
+    SlippageExceeded,
+
+    #[error("Unknown token: {0}")]
+    UnknownToken(String),
+
+    #[error("Invalid token metadata: {0}")]
+    InvalidMetadata(String),
+
     #[error("Client error: {0}")]
     Client(#[from] jupiter_swap_api_client::ClientError),
 
+    #[error("Signer error: {0}")]
+    Signer(#[from] solana_sdk::signer::SignerError),
+
     #[error("Solana client error: {0}")]
     SolanaClient(#[from] solana_client::client_error::ClientError),
 }
+
+impl Error {
+    /// Tell the failures of a sent `transaction` a user can act on apart from
+    /// the rest.
+    pub fn from_send(
+        error: solana_client::client_error::ClientError,
+        transaction: &Transaction,
+    ) -> Self {
+        let classified = error
+            .get_transaction_error()
+            .and_then(|e| Self::from_transaction_error(&e, transaction));
+        classified.unwrap_or(Self::SolanaClient(error))
+    }
+
+    /// Custom error codes are only meaningful together with the program that
+    /// raised them, which is read from the failing instruction.
+    fn from_transaction_error(error: &TransactionError, transaction: &Transaction) -> Option<Self> {
+        match error {
+            TransactionError::InsufficientFundsForFee
+            | TransactionError::InsufficientFundsForRent { .. }
+            | TransactionError::AccountNotFound => {
+                Some(Self::InsufficientBalance(error.to_string()))
+            }
+            TransactionError::InstructionError(_, InstructionError::InsufficientFunds) => {
+                Some(Self::InsufficientBalance(error.to_string()))
+            }
+            TransactionError::InstructionError(index, InstructionError::Custom(code)) => {
+                let program = transaction.message.program_id(usize::from(*index))?;
+                match (*program, *code) {
+                    (
+                        SYSTEM_PROGRAM_ID | TOKEN_PROGRAM_ID | TOKEN_2022_PROGRAM_ID,
+                        INSUFFICIENT_FUNDS,
+                    ) => Some(Self::InsufficientBalance(error.to_string())),
+                    (JUPITER_V6_PROGRAM_ID, JUPITER_SLIPPAGE_TOLERANCE_EXCEEDED) => {
+                        Some(Self::SlippageExceeded)
+                    }
+                    (TRENCH_PROGRAM_ID, code) if TRENCH_SLIPPAGE.contains(&code) => {
+                        Some(Self::SlippageExceeded)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// A quote Jupiter refused because it can't route one of the mints.
+    pub fn from_quote(error: jupiter_swap_api_client::ClientError) -> Self {
+        let message = error.to_string();
+        if UNROUTABLE.iter().any(|marker| message.contains(marker)) {
+            Self::UnknownToken(message)
+        } else {
+            Self::Client(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+    use solana_system_interface::instruction as system_instruction;
+
+    use super::*;
+
+    fn transaction(program_id: Pubkey) -> Transaction {
+        let payer = Pubkey::new_unique();
+        Transaction::new_with_payer(
+            &[
+                system_instruction::transfer(&payer, &Pubkey::new_unique(), 1),
+                Instruction::new_with_bytes(program_id, &[], vec![]),
+            ],
+            Some(&payer),
+        )
+    }
+
+    #[test]
+    fn test_from_transaction_error() {
+        let classify =
+            |error, program_id| Error::from_transaction_error(&error, &transaction(program_id));
+
+        assert!(matches!(
+            classify(
+                TransactionError::InsufficientFundsForFee,
+                Pubkey::new_unique()
+            ),
+            Some(Error::InsufficientBalance(_))
+        ));
+        assert!(matches!(
+            classify(
+                TransactionError::InstructionError(0, InstructionError::Custom(INSUFFICIENT_FUNDS)),
+                Pubkey::new_unique()
+            ),
+            Some(Error::InsufficientBalance(_))
+        ));
+        assert!(matches!(
+            classify(
+                TransactionError::InstructionError(
+                    1,
+                    InstructionError::Custom(JUPITER_SLIPPAGE_TOLERANCE_EXCEEDED)
+                ),
+                JUPITER_V6_PROGRAM_ID
+            ),
+            Some(Error::SlippageExceeded)
+        ));
+        assert!(matches!(
+            classify(
+                TransactionError::InstructionError(1, InstructionError::Custom(6002)),
+                TRENCH_PROGRAM_ID
+            ),
+            Some(Error::SlippageExceeded)
+        ));
+        // The same code from another program means something else
+        assert!(classify(
+            TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(JUPITER_SLIPPAGE_TOLERANCE_EXCEEDED)
+            ),
+            Pubkey::new_unique()
+        )
+        .is_none());
+        assert!(classify(
+            TransactionError::InstructionError(1, InstructionError::Custom(INSUFFICIENT_FUNDS)),
+            Pubkey::new_unique()
+        )
+        .is_none());
+        assert!(classify(TransactionError::BlockhashNotFound, Pubkey::new_unique()).is_none());
+    }
+}
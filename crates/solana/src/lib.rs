@@ -3,5 +3,8 @@ pub mod client;
 pub mod constants;
 pub mod error;
 pub mod fresh_hash;
+pub mod signer;
+pub mod sweep;
+pub mod token;
 
 pub use jupiter_swap_api_client::quote::QuoteResponse;
@@ -0,0 +1,64 @@
+use std::future::Future;
+
+use solana_sdk::{pubkey::Pubkey, transaction::Transaction};
+use x_link_types::account::Account;
+
+use crate::error::Error;
+
+/// A wallet that transactions are built for and signed by.
+///
+/// [`Account`] signs in process. Implementations backed by the signer daemon
+/// keep the key out of this process entirely.
+pub trait WalletSigner: Send + Sync {
+    fn pubkey(&self) -> Pubkey;
+
+    /// Add this wallet's signature to `transaction`, whose recent blockhash
+    /// must already be set.
+    fn sign_transaction(
+        &self,
+        transaction: &mut Transaction,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+impl WalletSigner for Account {
+    fn pubkey(&self) -> Pubkey {
+        solana_sdk::signer::Signer::pubkey(self)
+    }
+
+    async fn sign_transaction(&self, transaction: &mut Transaction) -> Result<(), Error> {
+        let recent_blockhash = transaction.message.recent_blockhash;
+        Ok(transaction.try_partial_sign(&[self], recent_blockhash)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{hash::Hash, signature::Keypair};
+    use solana_system_interface::instruction as system_instruction;
+    use x_link_types::{social::SocialId, twitter::TwitterId};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_account_signer() {
+        let account = Account::new(
+            SocialId::Twitter(TwitterId::new(123456).unwrap()),
+            Keypair::new(),
+            1,
+        );
+        let pubkey = WalletSigner::pubkey(&account);
+        let mut transaction = Transaction::new_with_payer(
+            &[system_instruction::transfer(
+                &pubkey,
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(&pubkey),
+        );
+        transaction.message.recent_blockhash = Hash::new_unique();
+
+        account.sign_transaction(&mut transaction).await.unwrap();
+        assert!(transaction.is_signed());
+        assert!(transaction.verify().is_ok());
+    }
+}
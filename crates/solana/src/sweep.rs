@@ -0,0 +1,314 @@
+//! Moving everything a wallet holds, SOL and every SPL token, to another
+//! wallet. Used to rotate the master seed.
+
+use serde::Serialize;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_request::TokenAccountsFilter,
+    rpc_response::RpcKeyedAccount,
+};
+use solana_sdk::{
+    instruction::Instruction, pubkey::Pubkey, signature::Signature, transaction::Transaction,
+};
+use solana_system_interface::instruction as system_instruction;
+
+use crate::{
+    constants::{
+        LAMPORTS_PER_SIGNATURE, TOKEN_2022_PROGRAM_ID, TOKEN_ACCOUNT_RENT, TOKEN_PROGRAM_ID,
+    },
+    error::Error,
+    signer::WalletSigner,
+    token,
+};
+
+/// Token accounts moved per transaction, each takes three instructions.
+const TOKENS_PER_TRANSACTION: usize = 4;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenHolding {
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub account: Pubkey,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub mint: Pubkey,
+    /// Token or Token-2022
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub program: Pubkey,
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+/// What a sweep would move, and what it costs the source wallet.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepPlan {
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub from: Pubkey,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub to: Pubkey,
+    pub lamports: u64,
+    /// Every token account, empty ones are closed for their rent
+    pub tokens: Vec<TokenHolding>,
+    pub transactions: usize,
+    pub fee_lamports: u64,
+    /// The wallet can't pay for its first token transaction, fund it with SOL first
+    pub underfunded: bool,
+}
+
+impl SweepPlan {
+    pub fn new(from: Pubkey, to: Pubkey, lamports: u64, tokens: Vec<TokenHolding>) -> Self {
+        let token_transactions = tokens.len().div_ceil(TOKENS_PER_TRANSACTION);
+        let transactions = token_transactions + usize::from(lamports > 0 || !tokens.is_empty());
+        let fee_lamports = transactions as u64 * LAMPORTS_PER_SIGNATURE;
+        // Later batches are paid for by the rent of the accounts closed before them
+        let first_batch = tokens.len().min(TOKENS_PER_TRANSACTION) as u64;
+        let underfunded = !tokens.is_empty()
+            && lamports < LAMPORTS_PER_SIGNATURE + first_batch * TOKEN_ACCOUNT_RENT;
+        Self {
+            from,
+            to,
+            lamports,
+            tokens,
+            transactions,
+            fee_lamports,
+            underfunded,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lamports == 0 && self.tokens.is_empty()
+    }
+
+    /// Instructions of the token transactions, in order. Each token account is
+    /// emptied into the destination's associated account and closed, its rent
+    /// going to the source so the final SOL transfer collects it.
+    pub fn token_batches(&self) -> Vec<Vec<Instruction>> {
+        self.tokens
+            .chunks(TOKENS_PER_TRANSACTION)
+            .map(|batch| {
+                batch
+                    .iter()
+                    .flat_map(|holding| {
+                        let mut instructions = Vec::with_capacity(3);
+                        if holding.amount > 0 {
+                            instructions.push(token::create_associated_token_account_idempotent(
+                                &self.from,
+                                &self.to,
+                                &holding.mint,
+                                &holding.program,
+                            ));
+                            instructions.push(token::transfer_checked(
+                                &holding.program,
+                                &holding.account,
+                                &holding.mint,
+                                &token::associated_token_address(
+                                    &self.to,
+                                    &holding.mint,
+                                    &holding.program,
+                                ),
+                                &self.from,
+                                holding.amount,
+                                holding.decimals,
+                            ));
+                        }
+                        instructions.push(token::close_account(
+                            &holding.program,
+                            &holding.account,
+                            &self.from,
+                            &self.from,
+                        ));
+                        instructions
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+pub struct Sweeper {
+    sol: RpcClient,
+}
+
+impl Sweeper {
+    pub fn new(rpc_url: String) -> Self {
+        Self {
+            sol: RpcClient::new(rpc_url),
+        }
+    }
+
+    /// Everything `from` currently holds, to be moved to `to`.
+    pub async fn plan(&self, from: Pubkey, to: Pubkey) -> Result<SweepPlan, Error> {
+        let lamports = self.sol.get_balance(&from).await?;
+        let mut tokens = Vec::new();
+        for program in [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID] {
+            for keyed in self
+                .sol
+                .get_token_accounts_by_owner(&from, TokenAccountsFilter::ProgramId(program))
+                .await?
+            {
+                tokens.push(parse_token_account(&keyed, program)?);
+            }
+        }
+        Ok(SweepPlan::new(from, to, lamports, tokens))
+    }
+
+    /// Move everything `wallet` holds to `to`, returning the signature of
+    /// every transaction sent. Balances are read again first, so a sweep
+    /// interrupted halfway can simply be run again.
+    #[tracing::instrument(skip(self, wallet), fields(wallet = %wallet.pubkey()))]
+    pub async fn sweep(
+        &self,
+        wallet: &impl WalletSigner,
+        to: &Pubkey,
+    ) -> Result<Vec<Signature>, Error> {
+        let from = wallet.pubkey();
+        let plan = self.plan(from, *to).await?;
+        let mut signatures = Vec::new();
+        for instructions in plan.token_batches() {
+            signatures.push(self.send(wallet, &instructions).await?);
+        }
+
+        // Whatever is left after the token transactions, rent refunds included
+        let lamports = self.sol.get_balance(&from).await?;
+        if lamports > LAMPORTS_PER_SIGNATURE {
+            let transfer =
+                system_instruction::transfer(&from, to, lamports - LAMPORTS_PER_SIGNATURE);
+            signatures.push(self.send(wallet, &[transfer]).await?);
+        }
+        tracing::debug!(transactions = signatures.len(), "Swept");
+        Ok(signatures)
+    }
+
+    async fn send(
+        &self,
+        wallet: &impl WalletSigner,
+        instructions: &[Instruction],
+    ) -> Result<Signature, Error> {
+        let mut transaction = Transaction::new_with_payer(instructions, Some(&wallet.pubkey()));
+        transaction.message.recent_blockhash = self.sol.get_latest_blockhash().await?;
+        wallet.sign_transaction(&mut transaction).await?;
+        self.sol
+            .send_and_confirm_transaction(&transaction)
+            .await
+            .map_err(|e| Error::from_send(e, &transaction))
+    }
+}
+
+/// Read a token account returned with the `jsonParsed` encoding.
+fn parse_token_account(keyed: &RpcKeyedAccount, program: Pubkey) -> Result<TokenHolding, Error> {
+    let invalid =
+        |what: &str| Error::Generic(format!("token account {}: invalid {}", keyed.pubkey, what));
+    let account =
+        serde_json::to_value(&keyed.account).map_err(|e| Error::Generic(e.to_string()))?;
+    let info = &account["data"]["parsed"]["info"];
+    let amount = &info["tokenAmount"];
+    Ok(TokenHolding {
+        account: keyed.pubkey.parse().map_err(|_| invalid("address"))?,
+        mint: info["mint"]
+            .as_str()
+            .and_then(|mint| mint.parse().ok())
+            .ok_or_else(|| invalid("mint"))?,
+        program,
+        amount: amount["amount"]
+            .as_str()
+            .and_then(|amount| amount.parse().ok())
+            .ok_or_else(|| invalid("amount"))?,
+        decimals: amount["decimals"]
+            .as_u64()
+            .and_then(|decimals| u8::try_from(decimals).ok())
+            .ok_or_else(|| invalid("decimals"))?,
+    })
+}
+
+fn serialize_pubkey<S: serde::Serializer>(
+    pubkey: &Pubkey,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(pubkey)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding(amount: u64) -> TokenHolding {
+        TokenHolding {
+            account: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            program: TOKEN_PROGRAM_ID,
+            amount,
+            decimals: 6,
+        }
+    }
+
+    #[test]
+    fn test_plan() {
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(SweepPlan::new(from, to, 0, vec![]).is_empty());
+        assert_eq!(SweepPlan::new(from, to, 0, vec![]).transactions, 0);
+
+        let plan = SweepPlan::new(from, to, 1_000_000, vec![]);
+        assert_eq!(plan.transactions, 1);
+        assert!(!plan.underfunded);
+
+        let mut tokens = (0..5).map(|_| holding(10)).collect::<Vec<_>>();
+        tokens.push(holding(0));
+        let plan = SweepPlan::new(from, to, 0, tokens);
+        assert_eq!(plan.transactions, 3);
+        assert_eq!(plan.fee_lamports, 3 * LAMPORTS_PER_SIGNATURE);
+        assert!(plan.underfunded);
+
+        let batches = plan.token_batches();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].len(), 4 * 3);
+        // The empty account is only closed
+        assert_eq!(batches[1].len(), 3 + 1);
+        // Rent goes back to the source, the SOL transfer sweeps it
+        assert_eq!(batches[1][3].accounts[1].pubkey, from);
+    }
+
+    #[test]
+    fn test_parse_token_account() {
+        let mint = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let keyed = serde_json::from_value::<RpcKeyedAccount>(serde_json::json!({
+            "pubkey": account.to_string(),
+            "account": {
+                "lamports": TOKEN_ACCOUNT_RENT,
+                "data": {
+                    "program": "spl-token",
+                    "parsed": {
+                        "type": "account",
+                        "info": {
+                            "mint": mint.to_string(),
+                            "owner": Pubkey::new_unique().to_string(),
+                            "state": "initialized",
+                            "isNative": false,
+                            "tokenAmount": {
+                                "amount": "1500000",
+                                "decimals": 6,
+                                "uiAmount": 1.5,
+                                "uiAmountString": "1.5"
+                            }
+                        }
+                    },
+                    "space": 165
+                },
+                "owner": TOKEN_PROGRAM_ID.to_string(),
+                "executable": false,
+                "rentEpoch": 18446744073709551615u64,
+                "space": 165
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            parse_token_account(&keyed, TOKEN_PROGRAM_ID).unwrap(),
+            TokenHolding {
+                account,
+                mint,
+                program: TOKEN_PROGRAM_ID,
+                amount: 1_500_000,
+                decimals: 6,
+            }
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Instructions shared by the Token and Token-2022 programs, built by hand
+//! since both programs take the same layout for them.
+
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+use crate::constants::{ASSOCIATED_TOKEN_PROGRAM_ID, SYSTEM_PROGRAM_ID};
+
+const TRANSFER_CHECKED: u8 = 12;
+const CLOSE_ACCOUNT: u8 = 9;
+const CREATE_IDEMPOTENT: u8 = 1;
+
+/// Address of `wallet`'s associated account for `mint` under the token `program`.
+pub fn associated_token_address(wallet: &Pubkey, mint: &Pubkey, program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Create `wallet`'s associated token account for `mint`, doing nothing if it exists.
+pub fn create_associated_token_account_idempotent(
+    payer: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    program: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        ASSOCIATED_TOKEN_PROGRAM_ID,
+        &[CREATE_IDEMPOTENT],
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(wallet, mint, program), false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(*program, false),
+        ],
+    )
+}
+
+pub fn transfer_checked(
+    program: &Pubkey,
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Instruction {
+    let mut data = vec![TRANSFER_CHECKED];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(decimals);
+    Instruction::new_with_bytes(
+        *program,
+        &data,
+        vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+    )
+}
+
+/// Close an empty token account, its rent goes to `destination`.
+pub fn close_account(
+    program: &Pubkey,
+    account: &Pubkey,
+    destination: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program,
+        &[CLOSE_ACCOUNT],
+        vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::TOKEN_PROGRAM_ID;
+
+    #[test]
+    fn test_transfer_checked() {
+        let instruction = transfer_checked(
+            &TOKEN_PROGRAM_ID,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            0x0102,
+            6,
+        );
+        assert_eq!(instruction.data, [12, 2, 1, 0, 0, 0, 0, 0, 0, 6]);
+        assert!(instruction.accounts[3].is_signer);
+    }
+}
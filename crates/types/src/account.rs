@@ -1,43 +1,137 @@
-use serde::{ser::SerializeStruct as _, Serialize, Serializer};
-use solana_sdk::{signature::Keypair, signer::Signer as _};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::{Signer, SignerError},
+};
+use x_link_utils::locked::Locked;
 
+use crate::{chain::Chain, social::SocialId};
+
+/// A social user's wallet. The keypair lives in locked memory and is only
+/// reachable through [`Signer`], share [`Account::info`] instead.
 pub struct Account {
-    pub twitter_id: u64,
-    pub wallet: Keypair,
+    pub id: SocialId,
+    /// Which of the user's wallets this is, 0 is their main wallet
+    pub wallet_index: u32,
+    wallet: Locked<Keypair>,
+    /// Version of the derivation scheme that produced `wallet`
+    pub scheme: u8,
+    /// When the wallet was derived, in seconds since the Unix epoch
+    pub created_at: u64,
+}
+
+/// The public side of a wallet on any chain, as returned by `getAccount`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    /// `platform` and `id`
+    #[serde(flatten)]
+    pub id: SocialId,
+    /// Which of the user's wallets this is, 0 is their main wallet
+    pub wallet_index: u32,
+    pub chain: Chain,
+    /// Address in the chain's own format, base58 on Solana and EIP-55 hex on EVM chains
+    pub pubkey: String,
+    /// Version of the derivation scheme that produced `pubkey`, EVM wallets
+    /// only have one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<u8>,
+    /// When the wallet was derived, in seconds since the Unix epoch
+    pub created_at: u64,
+    /// When the wallet's private key was exported, in seconds since the Unix
+    /// epoch. It may be controlled by someone else since
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exported_at: Option<u64>,
+}
+
+impl AccountInfo {
+    pub fn new(id: SocialId, wallet_index: u32, chain: Chain, pubkey: String) -> Self {
+        Self {
+            id,
+            wallet_index,
+            chain,
+            pubkey,
+            scheme: None,
+            created_at: now(),
+            exported_at: None,
+        }
+    }
+
+    pub fn with_scheme(mut self, scheme: u8) -> Self {
+        self.scheme = Some(scheme);
+        self
+    }
+
+    pub fn with_created_at(mut self, created_at: u64) -> Self {
+        self.created_at = created_at;
+        self
+    }
+
+    pub fn with_exported_at(mut self, exported_at: u64) -> Self {
+        self.exported_at = Some(exported_at);
+        self
+    }
 }
 
 impl std::fmt::Debug for Account {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Account")
-            .field("twitter_id", &self.twitter_id)
-            .field("wallet", &self.wallet.pubkey().to_string())
+            .field("id", &self.id)
+            .field("wallet_index", &self.wallet_index)
+            .field("wallet", &self.pubkey().to_string())
+            .field("scheme", &self.scheme)
+            .field("created_at", &self.created_at)
             .finish()
     }
 }
 
 impl Account {
-    pub fn new(twitter_id: u64, wallet: Keypair) -> Self {
-        Self { twitter_id, wallet }
+    pub fn new(id: SocialId, wallet: Keypair, scheme: u8) -> Self {
+        Self {
+            id,
+            wallet_index: 0,
+            wallet: Locked::new(wallet),
+            scheme,
+            created_at: now(),
+        }
     }
-}
 
-impl std::ops::Deref for Account {
-    type Target = Keypair;
+    /// Mark the account as one of the user's sub-wallets.
+    pub fn with_wallet_index(mut self, wallet_index: u32) -> Self {
+        self.wallet_index = wallet_index;
+        self
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.wallet
+    pub fn info(&self) -> AccountInfo {
+        AccountInfo::new(
+            self.id,
+            self.wallet_index,
+            Chain::Solana,
+            self.pubkey().to_string(),
+        )
+        .with_scheme(self.scheme)
+        .with_created_at(self.created_at)
     }
 }
 
-impl Serialize for Account {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut state = serializer.serialize_struct("Account", 2)?;
-        state.serialize_field("twitter_id", &self.twitter_id)?;
-        state.serialize_field("wallet", &self.wallet.pubkey().to_string())?;
-        state.end()
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+impl Signer for Account {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        self.wallet.as_ref().try_pubkey()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        self.wallet.as_ref().try_sign_message(message)
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
     }
 }
 
@@ -47,18 +141,79 @@ mod tests {
 
     use super::*;
 
+    static_assertions::assert_not_impl_any!(Account: std::ops::Deref, Clone, Serialize);
+
+    fn twitter_id(id: u64) -> SocialId {
+        crate::twitter::TwitterId::new(id).unwrap().into()
+    }
+
     #[test]
-    fn test_serialize_account() {
-        let account = Account {
-            twitter_id: 123456,
-            wallet: Keypair::new(),
-        };
+    fn test_account_info() {
+        let account = Account::new(twitter_id(123456), Keypair::new(), 1);
+        let info = account.info();
 
         let expected = json!({
-            "twitter_id": 123456,
-            "wallet": account.wallet.pubkey().to_string(),
+            "platform": "twitter",
+            "id": "123456",
+            "walletIndex": 0,
+            "chain": "solana",
+            "pubkey": account.pubkey().to_string(),
+            "scheme": 1,
+            "createdAt": account.created_at,
         });
 
-        assert_eq!(serde_json::to_value(&account).unwrap(), expected);
+        assert_eq!(serde_json::to_value(&info).unwrap(), expected);
+        assert_eq!(
+            serde_json::from_value::<AccountInfo>(expected).unwrap(),
+            info
+        );
+
+        let account =
+            Account::new(SocialId::Telegram(123456), Keypair::new(), 3).with_wallet_index(2);
+        let info = account.info();
+
+        let expected = json!({
+            "platform": "telegram",
+            "id": "123456",
+            "walletIndex": 2,
+            "chain": "solana",
+            "pubkey": account.pubkey().to_string(),
+            "scheme": 3,
+            "createdAt": account.created_at,
+        });
+
+        assert_eq!(serde_json::to_value(&info).unwrap(), expected);
+        assert_eq!(
+            serde_json::from_value::<AccountInfo>(expected).unwrap(),
+            info
+        );
+
+        let info = AccountInfo::new(
+            twitter_id(1722992406616756224),
+            0,
+            Chain::Evm,
+            "0xed1B0701536Db26BB2BC2059C9C2ada1aD21B235".to_string(),
+        )
+        .with_created_at(1_700_000_000);
+        let json = serde_json::to_string(&info).unwrap();
+        assert!(json.contains(r#""id":"1722992406616756224""#));
+        assert!(!json.contains("scheme"));
+        assert!(!json.contains("exportedAt"));
+        assert_eq!(serde_json::from_str::<AccountInfo>(&json).unwrap(), info);
+
+        let info = info.with_exported_at(1_700_000_100);
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["exportedAt"], 1_700_000_100);
+        assert_eq!(serde_json::from_value::<AccountInfo>(json).unwrap(), info);
+    }
+
+    #[test]
+    fn test_signer() {
+        let keypair = Keypair::new();
+        let expected = keypair.sign_message(b"message");
+        let account = Account::new(twitter_id(123456), keypair, 1);
+
+        assert_eq!(account.sign_message(b"message"), expected);
+        assert!(expected.verify(account.pubkey().as_ref(), b"message"));
     }
 }
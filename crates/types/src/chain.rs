@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+/// A chain wallets are derived for. Every chain's wallets come from the same
+/// master seed.
+#[derive(
+    Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum Chain {
+    #[default]
+    Solana,
+    /// Ethereum and the chains sharing its addresses, such as Base
+    Evm,
+}
+
+impl Chain {
+    pub const ALL: [Self; 2] = [Self::Solana, Self::Evm];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Solana => "solana",
+            Self::Evm => "evm",
+        }
+    }
+}
+
+impl std::fmt::Display for Chain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for Chain {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|chain| chain.name() == s)
+            .ok_or_else(|| format!("unknown chain {:?}, expected solana or evm", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain() {
+        for chain in Chain::ALL {
+            assert_eq!(chain.to_string().parse::<Chain>().unwrap(), chain);
+            assert_eq!(
+                serde_json::to_value(chain).unwrap(),
+                serde_json::json!(chain.name())
+            );
+        }
+        assert_eq!(Chain::default(), Chain::Solana);
+        assert!("base".parse::<Chain>().is_err());
+    }
+}
@@ -1 +1,4 @@
 pub mod account;
+pub mod chain;
+pub mod social;
+pub mod twitter;
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+
+use crate::twitter::{IdVisitor, InvalidTwitterId, TwitterId};
+
+/// A social platform users link wallets from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum Platform {
+    Twitter,
+    Discord,
+    Telegram,
+    Farcaster,
+}
+
+impl Platform {
+    pub const ALL: [Self; 4] = [
+        Self::Twitter,
+        Self::Discord,
+        Self::Telegram,
+        Self::Farcaster,
+    ];
+
+    /// Lowercase name, also used as the platform's derivation label.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Twitter => "twitter",
+            Self::Discord => "discord",
+            Self::Telegram => "telegram",
+            Self::Farcaster => "farcaster",
+        }
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A user ID on one of the supported platforms. The same number on two
+/// platforms is two different users.
+///
+/// Serializes as `platform` and `id`, the ID as a decimal string like
+/// [`TwitterId`] since snowflakes exceed what a JavaScript number holds
+/// exactly. Numbers are accepted up to 2^53 - 1, twitter IDs are checked
+/// like any [`TwitterId`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(into = "RawSocialId", try_from = "RawSocialId")]
+pub enum SocialId {
+    Twitter(TwitterId),
+    /// Discord snowflake
+    Discord(u64),
+    Telegram(u64),
+    /// Farcaster FID
+    Farcaster(u64),
+}
+
+impl SocialId {
+    /// Fails only for an out of range twitter ID.
+    pub fn new(platform: Platform, id: u64) -> Result<Self, InvalidTwitterId> {
+        Ok(match platform {
+            Platform::Twitter => Self::Twitter(TwitterId::new(id)?),
+            Platform::Discord => Self::Discord(id),
+            Platform::Telegram => Self::Telegram(id),
+            Platform::Farcaster => Self::Farcaster(id),
+        })
+    }
+
+    pub fn platform(&self) -> Platform {
+        match self {
+            Self::Twitter(_) => Platform::Twitter,
+            Self::Discord(_) => Platform::Discord,
+            Self::Telegram(_) => Platform::Telegram,
+            Self::Farcaster(_) => Platform::Farcaster,
+        }
+    }
+
+    /// The ID within its platform.
+    pub fn id(&self) -> u64 {
+        match self {
+            Self::Twitter(id) => id.get(),
+            Self::Discord(id) | Self::Telegram(id) | Self::Farcaster(id) => *id,
+        }
+    }
+
+    pub fn twitter_id(&self) -> Option<TwitterId> {
+        match self {
+            Self::Twitter(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawSocialId {
+    platform: Platform,
+    #[serde(serialize_with = "serialize_id", deserialize_with = "deserialize_id")]
+    id: u64,
+}
+
+impl From<SocialId> for RawSocialId {
+    fn from(id: SocialId) -> Self {
+        Self {
+            platform: id.platform(),
+            id: id.id(),
+        }
+    }
+}
+
+impl TryFrom<RawSocialId> for SocialId {
+    type Error = InvalidTwitterId;
+
+    fn try_from(raw: RawSocialId) -> Result<Self, Self::Error> {
+        Self::new(raw.platform, raw.id)
+    }
+}
+
+fn serialize_id<S: serde::Serializer>(id: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(id)
+}
+
+fn deserialize_id<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    deserializer.deserialize_any(IdVisitor("platform ID"))
+}
+
+impl From<TwitterId> for SocialId {
+    fn from(id: TwitterId) -> Self {
+        Self::Twitter(id)
+    }
+}
+
+impl std::fmt::Display for SocialId {
+    /// `discord:80351110224678912`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.platform(), self.id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_social_id() {
+        for platform in Platform::ALL {
+            let id = SocialId::new(platform, 42).unwrap();
+            assert_eq!(id.platform(), platform);
+            assert_eq!(id.id(), 42);
+        }
+        let twitter_id = TwitterId::new(42).unwrap();
+        assert_eq!(SocialId::from(twitter_id).twitter_id(), Some(twitter_id));
+        assert_eq!(SocialId::Discord(42).twitter_id(), None);
+        assert_ne!(SocialId::from(twitter_id), SocialId::Discord(42));
+        assert!(SocialId::new(Platform::Twitter, 0).is_err());
+        assert_eq!(
+            SocialId::new(Platform::Discord, u64::MAX).unwrap().id(),
+            u64::MAX
+        );
+
+        let id = SocialId::Discord(80351110224678912);
+        assert_eq!(id.to_string(), "discord:80351110224678912");
+        assert_eq!(
+            serde_json::to_value(id).unwrap(),
+            json!({ "platform": "discord", "id": "80351110224678912" })
+        );
+        assert_eq!(
+            serde_json::from_value::<SocialId>(
+                json!({ "platform": "discord", "id": "80351110224678912" })
+            )
+            .unwrap(),
+            id
+        );
+        assert_eq!(
+            serde_json::from_value::<SocialId>(json!({ "platform": "farcaster", "id": 3 }))
+                .unwrap(),
+            SocialId::Farcaster(3)
+        );
+
+        for invalid in [
+            // May have been rounded on the way
+            json!({ "platform": "discord", "id": 80351110224678912u64 }),
+            json!({ "platform": "discord", "id": "" }),
+            json!({ "platform": "discord", "id": "+1" }),
+            json!({ "platform": "discord", "id": -1 }),
+            json!({ "platform": "discord", "id": "18446744073709551616" }),
+            json!({ "platform": "myspace", "id": "1" }),
+            // Refused as a twitter ID anywhere else
+            json!({ "platform": "twitter", "id": "0" }),
+            json!({ "platform": "twitter", "id": (i64::MAX as u64 + 1).to_string() }),
+        ] {
+            assert!(
+                serde_json::from_value::<SocialId>(invalid.clone()).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+
+/// Largest integer a JavaScript number holds exactly, 2^53 - 1.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// A twitter user ID, a positive signed 64 bit snowflake.
+///
+/// IDs exceed what a JavaScript number holds exactly, so they always
+/// serialize as a decimal string. Either a string or a number is accepted,
+/// numbers only up to 2^53 - 1 since a client may already have rounded
+/// anything larger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TwitterId(u64);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidTwitterId {
+    /// Not a decimal integer
+    Format(String),
+    /// Zero or above `i64::MAX`
+    Range(u64),
+}
+
+impl TwitterId {
+    pub const MAX: Self = Self(i64::MAX as u64);
+
+    pub fn new(id: u64) -> Result<Self, InvalidTwitterId> {
+        match id {
+            1..=0x7fff_ffff_ffff_ffff => Ok(Self(id)),
+            _ => Err(InvalidTwitterId::Range(id)),
+        }
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0
+    }
+}
+
+impl TryFrom<u64> for TwitterId {
+    type Error = InvalidTwitterId;
+
+    fn try_from(id: u64) -> Result<Self, Self::Error> {
+        Self::new(id)
+    }
+}
+
+impl From<TwitterId> for u64 {
+    fn from(id: TwitterId) -> Self {
+        id.0
+    }
+}
+
+impl PartialEq<u64> for TwitterId {
+    fn eq(&self, other: &u64) -> bool {
+        self.0 == *other
+    }
+}
+
+impl std::str::FromStr for TwitterId {
+    type Err = InvalidTwitterId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // u64's parser takes a leading `+`, IDs never have one
+        if !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(InvalidTwitterId::Format(s.to_string()));
+        }
+        Self::new(
+            s.parse()
+                .map_err(|_| InvalidTwitterId::Format(s.to_string()))?,
+        )
+    }
+}
+
+impl std::fmt::Display for TwitterId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::fmt::Display for InvalidTwitterId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Format(s) => write!(f, "invalid twitter ID {:?}, expected a decimal integer", s),
+            Self::Range(id) => write!(f, "twitter ID {} is out of range", id),
+        }
+    }
+}
+
+impl std::error::Error for InvalidTwitterId {}
+
+impl Serialize for TwitterId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TwitterId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = deserializer.deserialize_any(IdVisitor("twitter ID"))?;
+        Self::new(id).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Reads a numeric ID, named by the field, from a decimal string or a
+/// number. Numbers are only accepted up to 2^53 - 1 since a client may
+/// already have rounded anything larger.
+pub(crate) struct IdVisitor(pub &'static str);
+
+impl serde::de::Visitor<'_> for IdVisitor {
+    type Value = u64;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a {} as a string or a number", self.0)
+    }
+
+    fn visit_str<E: serde::de::Error>(self, s: &str) -> Result<u64, E> {
+        // u64's parser takes a leading `+`, IDs never have one
+        if !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(E::custom(format!(
+                "invalid {} {:?}, expected a decimal integer",
+                self.0, s
+            )));
+        }
+        s.parse().map_err(|_| {
+            E::custom(format!(
+                "invalid {} {:?}, expected a decimal integer",
+                self.0, s
+            ))
+        })
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, id: u64) -> Result<u64, E> {
+        if id > MAX_SAFE_INTEGER {
+            return Err(E::custom(format!(
+                "{} {} is too large for a JSON number, send it as a string",
+                self.0, id
+            )));
+        }
+        Ok(id)
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, id: i64) -> Result<u64, E> {
+        match u64::try_from(id) {
+            Ok(id) => self.visit_u64(id),
+            Err(_) => Err(E::custom(format!(
+                "invalid {} {}, expected a decimal integer",
+                self.0, id
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_twitter_id() {
+        const ID: u64 = 1722992406616756224;
+
+        let id = serde_json::from_value::<TwitterId>(json!(ID.to_string())).unwrap();
+        assert_eq!(id, ID);
+        assert_eq!(
+            serde_json::to_value(id).unwrap(),
+            json!("1722992406616756224")
+        );
+        assert_eq!(
+            serde_json::from_value::<TwitterId>(json!(123456)).unwrap(),
+            123456
+        );
+        assert_eq!(
+            serde_json::from_value::<TwitterId>(json!(i64::MAX.to_string())).unwrap(),
+            TwitterId::MAX
+        );
+
+        for invalid in [
+            json!("0"),
+            json!(0),
+            json!(-1),
+            json!("-1"),
+            json!("+1"),
+            json!(""),
+            json!("12e3"),
+            json!(1.5),
+            json!((i64::MAX as u64 + 1).to_string()),
+            json!(u64::MAX.to_string()),
+            json!("18446744073709551616"),
+            // May have been rounded on the way
+            json!(ID),
+            json!(null),
+        ] {
+            assert!(
+                serde_json::from_value::<TwitterId>(invalid.clone()).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+        assert_eq!(
+            serde_json::from_value::<TwitterId>(json!(MAX_SAFE_INTEGER)).unwrap(),
+            MAX_SAFE_INTEGER
+        );
+    }
+}
@@ -1 +1,2 @@
+pub mod locked;
 pub mod logging;
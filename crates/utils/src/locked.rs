@@ -0,0 +1,125 @@
+//! Heap memory for seeds and keys that is locked against swap and wiped on drop.
+
+use std::{alloc::Layout, ptr::NonNull};
+
+use zeroize::Zeroize as _;
+
+/// A value in its own page-aligned allocation, locked into RAM where the OS
+/// allows it.
+///
+/// On drop the value is dropped in place, then the whole allocation is zeroed
+/// before it's unlocked and freed. Every value gets whole pages to itself, so
+/// unlocking one never unlocks a neighbour.
+pub struct Locked<T> {
+    ptr: NonNull<T>,
+    layout: Layout,
+    lock: Option<region::LockGuard>,
+}
+
+// Locked owns its value like a Box does
+unsafe impl<T: Send> Send for Locked<T> {}
+unsafe impl<T: Sync> Sync for Locked<T> {}
+
+impl<T> Locked<T> {
+    /// Move `value` into locked memory. The moved-from bytes are left to the
+    /// caller, build secrets in place with [`Locked::as_mut`] where possible.
+    pub fn new(value: T) -> Self {
+        let page = region::page::size();
+        let layout = Layout::from_size_align(
+            std::mem::size_of::<T>().max(1).next_multiple_of(page),
+            std::mem::align_of::<T>().max(page),
+        )
+        .expect("page sized layout");
+
+        // SAFETY: the layout has a non-zero size
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) } as *mut T;
+        let ptr = NonNull::new(ptr).unwrap_or_else(|| std::alloc::handle_alloc_error(layout));
+
+        let lock = match region::lock(ptr.as_ptr(), layout.size()) {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                warn_unlocked(&e);
+                None
+            }
+        };
+
+        // SAFETY: ptr is valid for writes and aligned for T
+        unsafe { ptr.as_ptr().write(value) };
+        Self { ptr, layout, lock }
+    }
+
+    /// Whether the OS agreed to keep the memory out of swap.
+    pub fn is_locked(&self) -> bool {
+        self.lock.is_some()
+    }
+}
+
+impl<T> AsRef<T> for Locked<T> {
+    fn as_ref(&self) -> &T {
+        // SAFETY: ptr holds an initialized T for the lifetime of self
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> AsMut<T> for Locked<T> {
+    fn as_mut(&mut self) -> &mut T {
+        // SAFETY: ptr holds an initialized T for the lifetime of self
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T> Drop for Locked<T> {
+    fn drop(&mut self) {
+        // SAFETY: the value is dropped exactly once, then only its bytes are
+        // touched before the allocation is freed with the layout it was made with
+        unsafe {
+            std::ptr::drop_in_place(self.ptr.as_ptr());
+            std::slice::from_raw_parts_mut(self.ptr.as_ptr() as *mut u8, self.layout.size())
+                .zeroize();
+        }
+        self.lock.take();
+        unsafe { std::alloc::dealloc(self.ptr.as_ptr() as *mut u8, self.layout) };
+    }
+}
+
+impl<T> std::fmt::Debug for Locked<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Locked")
+            .field("locked", &self.is_locked())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Warn once, a low RLIMIT_MEMLOCK would otherwise log on every derivation.
+fn warn_unlocked(error: &region::Error) {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    WARNED.call_once(|| {
+        tracing::warn!(
+            %error,
+            "failed to lock secret memory, it may be swapped to disk. Raise RLIMIT_MEMLOCK to fix"
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locked() {
+        let mut locked = Locked::new([0u8; 64]);
+        locked.as_mut()[0] = 1;
+        assert_eq!(locked.as_ref()[0], 1);
+        assert_eq!(locked.ptr.as_ptr() as usize % region::page::size(), 0);
+        assert!(format!("{:?}", locked).starts_with("Locked"));
+    }
+
+    #[test]
+    fn test_drop_in_place() {
+        let counter = std::rc::Rc::new(());
+        let locked = Locked::new(counter.clone());
+        assert_eq!(std::rc::Rc::strong_count(&counter), 2);
+        drop(locked);
+        assert_eq!(std::rc::Rc::strong_count(&counter), 1);
+    }
+}
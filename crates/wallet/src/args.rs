@@ -0,0 +1,162 @@
+use crate::{error::Error, keygen::SecretSource, passphrase::PassphraseSource};
+
+// Command line flags choosing where the master seed is loaded from, shared
+// by the binaries that load it. Plain comments, a doc comment would replace
+// the about text of every command flattening these.
+#[derive(clap::Args, Debug, Clone)]
+pub struct SecretArgs {
+    /// Keystore or plaintext mnemonic
+    #[clap(long)]
+    pub secret_file: Option<String>,
+
+    /// Share file from `x-link-wallet shares split`, repeat up to the threshold
+    #[clap(long, conflicts_with = "secret_file")]
+    pub share_file: Vec<String>,
+
+    /// Read the passphrase of the --share-file at the same position from a
+    /// file only the current user can access, repeat once per share
+    #[clap(long, value_name = "PATH", requires = "share_file")]
+    pub share_passphrase_file: Vec<String>,
+
+    /// Read the passphrase of the --share-file at the same position from
+    /// this inherited file descriptor, repeat once per share
+    #[clap(
+        long,
+        value_name = "FD",
+        requires = "share_file",
+        conflicts_with = "share_passphrase_file"
+    )]
+    pub share_passphrase_fd: Vec<i32>,
+
+    #[clap(flatten)]
+    pub passphrase: PassphraseArgs,
+}
+
+// Command line flags choosing where the seed passphrase is read from, the
+// terminal prompt if none is given.
+#[derive(clap::Args, Debug, Clone)]
+#[group(multiple = false)]
+pub struct PassphraseArgs {
+    /// Read the passphrase from this environment variable
+    #[clap(long, value_name = "VAR")]
+    pub passphrase_env: Option<String>,
+
+    /// Read the passphrase from this inherited file descriptor
+    #[clap(long, value_name = "FD")]
+    pub passphrase_fd: Option<i32>,
+
+    /// Read the passphrase from a file only the current user can access
+    #[clap(long, value_name = "PATH")]
+    pub passphrase_file: Option<String>,
+}
+
+impl SecretArgs {
+    /// `None` if neither flag was given. Share passphrases are paired with
+    /// share files by position, all of them prompted for if none are given.
+    pub fn source(&self) -> Result<Option<SecretSource>, Error> {
+        let share_passphrases: Vec<_> = if !self.share_passphrase_file.is_empty() {
+            self.share_passphrase_file
+                .iter()
+                .map(|path| PassphraseSource::File(path.clone()))
+                .collect()
+        } else if !self.share_passphrase_fd.is_empty() {
+            self.share_passphrase_fd
+                .iter()
+                .map(|fd| PassphraseSource::Fd(*fd))
+                .collect()
+        } else {
+            vec![PassphraseSource::Prompt; self.share_file.len()]
+        };
+        if share_passphrases.len() != self.share_file.len() {
+            return Err(Error::Passphrase(format!(
+                "{} share passphrases given for {} share files, pass one per --share-file",
+                share_passphrases.len(),
+                self.share_file.len()
+            )));
+        }
+
+        Ok(match &self.secret_file {
+            Some(secret_file) => Some(SecretSource::File(secret_file.clone())),
+            None if !self.share_file.is_empty() => Some(SecretSource::Shares(
+                self.share_file
+                    .iter()
+                    .cloned()
+                    .zip(share_passphrases)
+                    .collect(),
+            )),
+            None => None,
+        })
+    }
+}
+
+impl PassphraseArgs {
+    pub fn source(&self) -> PassphraseSource {
+        if let Some(name) = &self.passphrase_env {
+            PassphraseSource::Env(name.clone())
+        } else if let Some(fd) = self.passphrase_fd {
+            PassphraseSource::Fd(fd)
+        } else if let Some(path) = &self.passphrase_file {
+            PassphraseSource::File(path.clone())
+        } else {
+            PassphraseSource::Prompt
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser as _;
+
+    use super::*;
+
+    #[derive(clap::Parser)]
+    struct Args {
+        #[clap(flatten)]
+        secret: SecretArgs,
+    }
+
+    fn source(args: &[&str]) -> Result<Option<SecretSource>, Error> {
+        Args::try_parse_from(std::iter::once("test").chain(args.iter().copied()))
+            .unwrap()
+            .secret
+            .source()
+    }
+
+    #[test]
+    fn test_share_passphrases() {
+        assert_eq!(
+            source(&[
+                "--share-file",
+                "a",
+                "--share-passphrase-fd",
+                "3",
+                "--share-file",
+                "b",
+                "--share-passphrase-fd",
+                "4",
+            ])
+            .unwrap(),
+            Some(SecretSource::Shares(vec![
+                ("a".to_string(), PassphraseSource::Fd(3)),
+                ("b".to_string(), PassphraseSource::Fd(4)),
+            ]))
+        );
+        assert_eq!(
+            source(&["--share-file", "a", "--share-file", "b"]).unwrap(),
+            Some(SecretSource::Shares(vec![
+                ("a".to_string(), PassphraseSource::Prompt),
+                ("b".to_string(), PassphraseSource::Prompt),
+            ]))
+        );
+        assert!(source(&[
+            "--share-file",
+            "a",
+            "--share-file",
+            "b",
+            "--share-passphrase-file",
+            "pa",
+        ])
+        .is_err());
+        assert_eq!(source(&[]).unwrap(), None);
+    }
+}
@@ -0,0 +1,111 @@
+//! Statements, signed by a derived wallet, that it belongs to a twitter ID or
+//! handle. Anyone with the attestation can check it, no seed needed.
+
+use std::str::FromStr as _;
+
+use serde::{Deserialize, Serialize};
+use solana_offchain_message::OffchainMessage;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer as _,
+};
+
+use crate::error::Error;
+
+/// Signed as a Solana off-chain message, so `solana verify-offchain-signature`
+/// checks it as well.
+const OFFCHAIN_VERSION: u8 = 0;
+const PREFIX: &str = "x-link attestation:";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Attestation {
+    /// The statement, naming the wallet, who it belongs to and when it was made
+    pub message: String,
+    pub pubkey: String,
+    /// Base58 signature by `pubkey` over `message` as an off-chain message
+    pub signature: String,
+}
+
+impl Attestation {
+    /// Attest that `keypair` is the wallet of `subject`, e.g. `twitter ID 42`.
+    pub fn sign(keypair: &Keypair, subject: &str, issued_at: u64) -> Result<Self, Error> {
+        let pubkey = keypair.pubkey().to_string();
+        let message = format!(
+            "{} {} is the wallet of {}, issued at unix time {}",
+            PREFIX, pubkey, subject, issued_at
+        );
+        let signature = Self::offchain_message(&message)?
+            .sign(keypair)
+            .map_err(|e| Error::Attestation(e.to_string()))?;
+        Ok(Self {
+            message,
+            pubkey,
+            signature: signature.to_string(),
+        })
+    }
+
+    /// Check the signature and that the statement is about the signing wallet.
+    pub fn verify(&self) -> Result<(), Error> {
+        let pubkey = Pubkey::from_str(&self.pubkey)
+            .map_err(|e| Error::Attestation(format!("invalid pubkey: {}", e)))?;
+        let signature = Signature::from_str(&self.signature)
+            .map_err(|e| Error::Attestation(format!("invalid signature: {}", e)))?;
+        if !self
+            .message
+            .starts_with(&format!("{} {} ", PREFIX, self.pubkey))
+        {
+            return Err(Error::Attestation(
+                "the message is not about the signing wallet".to_string(),
+            ));
+        }
+
+        let valid = Self::offchain_message(&self.message)?
+            .verify(&pubkey, &signature)
+            .map_err(|e| Error::Attestation(e.to_string()))?;
+        if !valid {
+            return Err(Error::Attestation("signature mismatch".to_string()));
+        }
+        Ok(())
+    }
+
+    fn offchain_message(message: &str) -> Result<OffchainMessage, Error> {
+        OffchainMessage::new(OFFCHAIN_VERSION, message.as_bytes())
+            .map_err(|e| Error::Attestation(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let keypair = Keypair::new();
+        let attestation = Attestation::sign(&keypair, "twitter ID 42", 1_700_000_000).unwrap();
+        assert_eq!(
+            attestation.message,
+            format!(
+                "x-link attestation: {} is the wallet of twitter ID 42, issued at unix time 1700000000",
+                keypair.pubkey()
+            )
+        );
+        assert!(attestation.verify().is_ok());
+
+        // Tampered statement
+        let mut tampered = attestation.clone();
+        tampered.message = tampered.message.replace("42", "43");
+        assert!(tampered.verify().is_err());
+
+        // Signed by another wallet
+        let mut other = Attestation::sign(&Keypair::new(), "twitter ID 42", 1_700_000_000).unwrap();
+        other.pubkey = attestation.pubkey.clone();
+        assert!(other.verify().is_err());
+
+        // Valid signature over a statement about someone else's wallet
+        let mut misattributed = attestation.clone();
+        misattributed.pubkey = Keypair::new().pubkey().to_string();
+        assert!(misattributed.verify().is_err());
+    }
+}
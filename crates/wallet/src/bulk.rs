@@ -0,0 +1,117 @@
+use rayon::prelude::*;
+use serde::Serialize;
+use solana_sdk::signer::Signer as _;
+
+use crate::keygen::KeyGen;
+
+/// A twitter ID's wallet address. Only ever the public half.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DerivedId {
+    pub twitter_id: u64,
+    pub pubkey: String,
+    /// Version of the scheme that derived `pubkey`
+    pub scheme: u8,
+}
+
+/// A handle's wallet address. Only ever the public half.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DerivedHandle {
+    pub handle: String,
+    pub pubkey: String,
+    /// Version of the scheme that derived `pubkey`
+    pub scheme: u8,
+}
+
+impl KeyGen {
+    /// Derive the wallet address of every ID in parallel, in input order.
+    pub fn derive_ids(&self, ids: &[u64]) -> Result<Vec<DerivedId>, Box<dyn std::error::Error>> {
+        ids.par_iter()
+            .map(|&twitter_id| {
+                let (scheme, keypair) = self
+                    .derive(twitter_id)
+                    .map_err(|e| format!("twitter ID {}: {}", twitter_id, e))?;
+                Ok(DerivedId {
+                    twitter_id,
+                    pubkey: keypair.pubkey().to_string(),
+                    scheme: scheme.version(),
+                })
+            })
+            .collect::<Result<_, String>>()
+            .map_err(Into::into)
+    }
+
+    /// Derive the wallet address of every handle in parallel, in input order.
+    pub fn derive_handles(
+        &self,
+        handles: &[String],
+    ) -> Result<Vec<DerivedHandle>, Box<dyn std::error::Error>> {
+        handles
+            .par_iter()
+            .map(|handle| {
+                let (scheme, keypair) = self
+                    .derive_handle(handle)
+                    .map_err(|e| format!("handle {:?}: {}", handle, e))?;
+                Ok(DerivedHandle {
+                    handle: handle.clone(),
+                    pubkey: keypair.pubkey().to_string(),
+                    scheme: scheme.version(),
+                })
+            })
+            .collect::<Result<_, String>>()
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheme_table::SchemeTable;
+
+    const SECRET: &[u8; 64] = b"What the fuck did you just fucking say about me you little bitch";
+
+    #[test]
+    fn test_derive_ids() {
+        let keygen = KeyGen::from(*SECRET);
+        let ids = (0..64).map(|i| i << 58 | i).collect::<Vec<u64>>();
+        let derived = keygen.derive_ids(&ids).unwrap();
+        for (id, entry) in ids.iter().zip(&derived) {
+            let (scheme, keypair) = keygen.derive(*id).unwrap();
+            assert_eq!(
+                *entry,
+                DerivedId {
+                    twitter_id: *id,
+                    pubkey: keypair.pubkey().to_string(),
+                    scheme: scheme.version(),
+                }
+            );
+        }
+
+        assert_eq!(
+            serde_json::to_value(&derived[1]).unwrap(),
+            serde_json::json!({
+                "twitterId": derived[1].twitter_id,
+                "pubkey": derived[1].pubkey,
+                "scheme": derived[1].scheme,
+            })
+        );
+    }
+
+    #[test]
+    fn test_derive_handles() {
+        let keygen = KeyGen::from(*SECRET).with_schemes(SchemeTable::fresh());
+        let handles = vec!["elonmusk".to_string(), "@Elon".to_string()];
+        let derived = keygen.derive_handles(&handles).unwrap();
+        assert_eq!(derived[0].handle, "elonmusk");
+        assert_eq!(derived[1].scheme, 3);
+        assert_eq!(
+            derived[1].pubkey,
+            keygen.derive_handle("elon").unwrap().1.pubkey().to_string()
+        );
+
+        assert!(keygen
+            .derive_handles(&["elon".to_string(), "".to_string()])
+            .is_err());
+    }
+}
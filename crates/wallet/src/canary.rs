@@ -0,0 +1,173 @@
+//! A canary stored next to the secret file so a mistyped passphrase is caught
+//! at startup instead of silently deriving a fresh set of empty wallets.
+
+use std::str::FromStr as _;
+
+use hmac::{Hmac, Mac as _};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signer::Signer as _};
+
+use crate::{error::Error, keygen::KeyGen, private_file, scheme::DerivationScheme};
+
+const FINGERPRINT_LABEL: &[u8] = b"x-link canary v1";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Canary {
+    /// A known twitter ID and the pubkey its V1 wallet must derive to, V1
+    /// since that holds whatever scheme table the seed is loaded with
+    #[serde(rename_all = "camelCase")]
+    Pubkey { twitter_id: u64, pubkey: String },
+    /// HMAC-SHA256 of a fixed label, keyed by the seed
+    Fingerprint { fingerprint: String },
+}
+
+impl Canary {
+    pub fn path_for(secret_file: &str) -> String {
+        format!("{}.canary", secret_file)
+    }
+
+    pub fn fingerprint(keygen: &KeyGen) -> Self {
+        Self::Fingerprint {
+            fingerprint: hex::encode(Self::compute_fingerprint(keygen)),
+        }
+    }
+
+    pub fn pubkey(keygen: &KeyGen, twitter_id: u64) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::Pubkey {
+            twitter_id,
+            pubkey: keygen
+                .derive_with_scheme(DerivationScheme::V1, twitter_id)?
+                .pubkey()
+                .to_string(),
+        })
+    }
+
+    /// Load the seed behind `secret_file` and store a canary for it, a pubkey
+    /// canary if `twitter_id` is given and a fingerprint otherwise.
+    pub fn create(
+        secret_file: &str,
+        passphrase: &str,
+        twitter_id: Option<u64>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if std::path::Path::new(&Self::path_for(secret_file)).exists() {
+            return Err(format!(
+                "refusing to overwrite existing canary: {}",
+                Self::path_for(secret_file)
+            )
+            .into());
+        }
+
+        let keygen = KeyGen::load_unchecked(secret_file, passphrase)?;
+        let canary = match twitter_id {
+            Some(twitter_id) => Self::pubkey(&keygen, twitter_id)?,
+            None => Self::fingerprint(&keygen),
+        };
+        canary.write(secret_file)?;
+        Ok(canary)
+    }
+
+    /// Re-derive the canary value from `keygen` and compare it to the stored one.
+    pub fn verify(&self, keygen: &KeyGen) -> Result<(), Box<dyn std::error::Error>> {
+        let matches = match self {
+            Self::Pubkey { twitter_id, pubkey } => {
+                keygen
+                    .derive_with_scheme(DerivationScheme::V1, *twitter_id)?
+                    .pubkey()
+                    == Pubkey::from_str(pubkey)?
+            }
+            Self::Fingerprint { fingerprint } => {
+                let expected = hex::decode(fingerprint)?;
+                let mut mac = Self::mac(keygen);
+                mac.update(FINGERPRINT_LABEL);
+                mac.verify_slice(&expected).is_ok()
+            }
+        };
+
+        if matches {
+            Ok(())
+        } else {
+            Err(Error::CanaryMismatch.into())
+        }
+    }
+
+    /// Read the canary stored next to `secret_file`, if there is one.
+    pub fn read(secret_file: &str) -> Result<Option<Self>, Error> {
+        match std::fs::read_to_string(Self::path_for(secret_file)) {
+            Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Atomically store the canary next to `secret_file`, readable by the
+    /// owner only.
+    pub fn write(&self, secret_file: &str) -> Result<(), Error> {
+        Ok(private_file::write_atomic(
+            &Self::path_for(secret_file),
+            &serde_json::to_vec_pretty(self)?,
+        )?)
+    }
+
+    fn compute_fingerprint(keygen: &KeyGen) -> [u8; 32] {
+        let mut mac = Self::mac(keygen);
+        mac.update(FINGERPRINT_LABEL);
+        mac.finalize().into_bytes().into()
+    }
+
+    fn mac(keygen: &KeyGen) -> Hmac<sha2::Sha256> {
+        Hmac::<sha2::Sha256>::new_from_slice(keygen.seed()).expect("hmac accepts any key length")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8; 64] = b"What the fuck did you just fucking say about me you little bitch";
+
+    #[test]
+    fn test_fingerprint_canary() {
+        let keygen = KeyGen::from(*SECRET);
+        let canary = Canary::fingerprint(&keygen);
+        assert!(canary.verify(&keygen).is_ok());
+
+        let mut wrong = *SECRET;
+        wrong[0] ^= 1;
+        assert!(canary.verify(&KeyGen::from(wrong)).is_err());
+    }
+
+    #[test]
+    fn test_pubkey_canary() {
+        let keygen = KeyGen::from(*SECRET);
+        let canary = Canary::pubkey(&keygen, 1722992406616756224).unwrap();
+        assert_eq!(
+            canary,
+            Canary::Pubkey {
+                twitter_id: 1722992406616756224,
+                pubkey: "ENuzcbEgZq9j9BQCSgsFfvnMeX8aY7xGDizGyf29eByN".to_string(),
+            }
+        );
+        assert!(canary.verify(&keygen).is_ok());
+
+        let mut wrong = *SECRET;
+        wrong[0] ^= 1;
+        assert!(canary.verify(&KeyGen::from(wrong)).is_err());
+    }
+
+    #[test]
+    fn test_serialize_canary() {
+        let canary = Canary::Pubkey {
+            twitter_id: 1,
+            pubkey: Pubkey::default().to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&canary).unwrap(),
+            serde_json::json!({
+                "type": "pubkey",
+                "twitterId": 1,
+                "pubkey": Pubkey::default().to_string(),
+            })
+        );
+    }
+}
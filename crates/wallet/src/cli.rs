@@ -1,19 +1,947 @@
+use solana_sdk::signer::Signer as _;
+use x_link_solana::{
+    constants::SOL_BASE_PATH,
+    sweep::{SweepPlan, Sweeper},
+};
+use x_link_types::{account::Account, chain::Chain, social::SocialId, twitter::TwitterId};
+use x_link_wallet::args::PassphraseArgs;
+use x_link_wallet::attestation::Attestation;
+use x_link_wallet::canary::Canary;
+use x_link_wallet::exports::{ExportRecord, Exports};
 use x_link_wallet::keygen::{KeyGen, KeyGenerator};
+use x_link_wallet::keystore::{KdfParams, Keystore};
+use x_link_wallet::mnemonic;
+use x_link_wallet::passphrase::PassphraseSource;
+use x_link_wallet::rotation::{Progress, RotationRecord, RotationStatus};
+use x_link_wallet::scheme::{DerivationScheme, MAX_WALLET_INDEX};
+use x_link_wallet::scheme_table::SchemeTable;
+use x_link_wallet::shares;
 
 #[derive(clap::Parser)]
+#[clap(args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    /// Without a subcommand, `--id` and `--secret-file` derive a key as `key` does
+    #[clap(flatten)]
+    key: Option<KeyArgs>,
+
+    #[clap(flatten)]
+    passphrase: PassphraseArgs,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Generate a new master secret, sealed in a keystore with a canary and a scheme table next to it
+    Init(InitArgs),
+
+    /// Derive the key for a twitter ID
+    Key(KeyArgs),
+
+    /// Derive the wallet addresses of many twitter IDs or handles, as CSV or JSON lines
+    Derive(DeriveArgs),
+
+    /// Write a twitter ID's private key to a file, handing custody to its user
+    Export(ExportArgs),
+
+    /// Check that a twitter ID or handle derives to a given wallet
+    Verify(VerifyArgs),
+
+    /// Store a canary next to the secret file so a wrong passphrase is rejected on load
+    Canary(CanaryArgs),
+
+    /// Plan the switch to the latest derivation scheme, as JSON lines, and store it with --write
+    Report(ReportArgs),
+
+    /// Move every wallet's SOL and SPL tokens to its wallet under a new master secret
+    Rotate(RotateArgs),
+
+    /// Manage encrypted keystores
+    #[clap(subcommand)]
+    Keystore(KeystoreCommand),
+
+    /// Split the mnemonic into k-of-n Shamir shares and recombine them
+    #[clap(subcommand)]
+    Shares(SharesCommand),
+}
+
+#[derive(clap::Args)]
+struct InitArgs {
+    /// Keystore to create
+    #[clap(long)]
+    secret_file: String,
+    /// Number of mnemonic words
+    #[clap(long, default_value_t = 24)]
+    words: usize,
+    /// Twitter ID whose wallet is recorded as the canary
+    #[clap(short, long, default_value_t = 0)]
+    id: u64,
+    #[clap(flatten)]
+    kdf: KdfArgs,
+}
+
+#[derive(clap::Args)]
+struct KeyArgs {
+    #[clap(short, long)]
+    id: u64,
+    #[clap(long)]
+    secret_file: String,
+    /// Chain to derive the wallet for, `solana` or `evm`
+    #[clap(long, default_value_t = Chain::Solana)]
+    chain: Chain,
+}
+
+#[derive(clap::Args)]
+struct DeriveArgs {
+    #[clap(long)]
+    secret_file: String,
+    /// File with one twitter ID per line, `-` for stdin, the default
+    #[clap(long)]
+    ids_file: Option<String>,
+    /// File with one twitter handle per line, `-` for stdin
+    #[clap(long, conflicts_with = "ids_file")]
+    handles_file: Option<String>,
+    #[clap(long, value_enum, default_value_t = Format::Csv)]
+    format: Format,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    /// With a header row
+    Csv,
+    /// One JSON object per line
+    Json,
+}
+
+#[derive(clap::Args)]
+struct ExportArgs {
+    #[clap(long)]
+    secret_file: String,
     #[clap(short, long)]
     id: u64,
+    /// File the key is written to, readable only by the current user
+    #[clap(long)]
+    out: String,
+    #[clap(long, value_enum, default_value_t = KeyFormat::Json)]
+    format: KeyFormat,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum KeyFormat {
+    /// Solana CLI keypair file, a JSON array of the 64 keypair bytes
+    Json,
+    /// Base58 of the 64 keypair bytes, as wallets import it
+    Base58,
+}
+
+#[derive(clap::Args)]
+struct VerifyArgs {
     #[clap(long)]
     secret_file: String,
+    #[clap(short, long, required_unless_present = "handle")]
+    id: Option<u64>,
+    #[clap(long, conflicts_with = "id")]
+    handle: Option<String>,
+    /// The wallet address to check
+    #[clap(long)]
+    pubkey: solana_sdk::pubkey::Pubkey,
+    /// On a match, also print an attestation signed by the wallet
+    #[clap(long)]
+    attest: bool,
+}
+
+#[derive(clap::Args)]
+struct CanaryArgs {
+    #[clap(long)]
+    secret_file: String,
+    /// Store this twitter ID and its pubkey instead of a seed fingerprint
+    #[clap(short, long)]
+    id: Option<u64>,
+}
+
+#[derive(clap::Args)]
+struct ReportArgs {
+    #[clap(long)]
+    secret_file: String,
+    /// File with every twitter ID served so far, one per line, oldest first
+    #[clap(long, required_unless_present = "handles_file")]
+    ids_file: Option<String>,
+    /// File with every twitter handle served so far, one per line, spelled as it was served
+    #[clap(long, conflicts_with = "ids_file")]
+    handles_file: Option<String>,
+    /// Store the planned schemes next to the secret file, switching IDs or
+    /// handles left out of the file to the latest scheme
+    #[clap(long)]
+    write: bool,
+}
+
+#[derive(clap::Args)]
+struct RotateArgs {
+    /// The secret wallets move away from
+    #[clap(long)]
+    secret_file: String,
+    /// The secret wallets move to, its passphrase is always prompted for
+    #[clap(long)]
+    new_secret_file: String,
+    /// File with one twitter ID per line
+    #[clap(long)]
+    ids_file: String,
+    /// JSON lines log of finished sweeps, wallets done in it are skipped on rerun
+    #[clap(long)]
+    progress_file: String,
+    /// Sweep every sub-wallet up to this index. Without it each ID's
+    /// sub-wallets are swept until `--gap-limit` empty ones in a row
+    #[clap(long)]
+    max_index: Option<u32>,
+    /// Empty sub-wallets in a row that end an ID's sweep, unless `--max-index` is given
+    #[clap(
+        long,
+        default_value_t = 20,
+        conflicts_with = "max_index",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    gap_limit: u32,
+    #[clap(long, default_value = SOL_BASE_PATH)]
+    rpc_url: String,
+    /// Send the sweeps, without it only the plan is printed
+    #[clap(long)]
+    execute: bool,
+}
+
+/// One line of the `rotate` plan.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlannedSweep<'a> {
+    twitter_id: u64,
+    wallet_index: u32,
+    #[serde(flatten)]
+    plan: &'a SweepPlan,
+}
+
+#[derive(clap::Subcommand)]
+enum KeystoreCommand {
+    /// Seal a mnemonic entered at the prompt into a new keystore
+    Create {
+        #[clap(long)]
+        out: String,
+        #[clap(flatten)]
+        kdf: KdfArgs,
+    },
+
+    /// Re-seal a keystore with a fresh salt and nonce, optionally with new KDF params
+    Reencrypt {
+        #[clap(long)]
+        keystore: String,
+        #[clap(flatten)]
+        kdf: KdfArgs,
+    },
+
+    /// Encrypt a plaintext mnemonic file into a keystore
+    Migrate {
+        #[clap(long)]
+        secret_file: String,
+        #[clap(long)]
+        out: String,
+        #[clap(flatten)]
+        kdf: KdfArgs,
+    },
+}
+
+#[derive(clap::Subcommand)]
+enum SharesCommand {
+    /// Split the mnemonic into share files, each sealed under its holder's passphrase
+    Split {
+        #[clap(long)]
+        secret_file: String,
+        /// Number of shares needed to recover the mnemonic
+        #[clap(long)]
+        threshold: u8,
+        /// Number of shares to create
+        #[clap(long)]
+        shares: u8,
+        /// Directory the share-<index>.json files are written to
+        #[clap(long)]
+        out_dir: String,
+        #[clap(flatten)]
+        kdf: KdfArgs,
+    },
+
+    /// Recombine share files into a single keystore
+    Combine {
+        /// Share file, repeat for every share at hand
+        #[clap(long, required = true)]
+        share_file: Vec<String>,
+        #[clap(long)]
+        out: String,
+        #[clap(flatten)]
+        kdf: KdfArgs,
+    },
+}
+
+#[derive(clap::Args)]
+struct KdfArgs {
+    /// Argon2id memory cost in KiB
+    #[clap(long)]
+    memory_kib: Option<u32>,
+    /// Argon2id iteration count
+    #[clap(long)]
+    iterations: Option<u32>,
+    /// Argon2id lanes
+    #[clap(long)]
+    parallelism: Option<u32>,
+}
+
+impl KdfArgs {
+    fn params(&self, base: KdfParams) -> KdfParams {
+        KdfParams {
+            memory_kib: self.memory_kib.unwrap_or(base.memory_kib),
+            iterations: self.iterations.unwrap_or(base.iterations),
+            parallelism: self.parallelism.unwrap_or(base.parallelism),
+        }
+    }
 }
 
 impl Args {
     pub fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let passphrase = rpassword::prompt_password("Enter passphrase: ")?;
+        let passphrase = self.passphrase.source();
+        let command = match (&self.command, &self.key) {
+            (Some(command), _) => command,
+            (None, Some(args)) => return args.run(&passphrase),
+            (None, None) => {
+                return Err("a subcommand, or --id and --secret-file, is required".into())
+            }
+        };
+        match command {
+            Command::Init(args) => args.run(&passphrase),
+            Command::Key(args) => args.run(&passphrase),
+            Command::Derive(args) => args.run(&passphrase),
+            Command::Export(args) => args.run(&passphrase),
+            Command::Verify(args) => args.run(&passphrase),
+            Command::Canary(args) => args.run(&passphrase),
+            Command::Report(args) => args.run(&passphrase),
+            Command::Rotate(args) => args.run(&passphrase),
+            Command::Keystore(command) => command.run(&passphrase),
+            Command::Shares(command) => command.run(&passphrase),
+        }
+    }
+}
+
+impl InitArgs {
+    fn run(&self, passphrase: &PassphraseSource) -> Result<(), Box<dyn std::error::Error>> {
+        refuse_overwrite(&self.secret_file)?;
+        refuse_overwrite(&Canary::path_for(&self.secret_file))?;
+        refuse_overwrite(&SchemeTable::path_for(&self.secret_file))?;
+
+        let mnemonic = mnemonic::generate(self.words)?;
+        let passphrase = passphrase.read_new()?;
+        let keygen = KeyGen::from(mnemonic.to_seed(passphrase.as_str()));
+        let canary = Canary::pubkey(&keygen, self.id)?;
+
+        Keystore::seal(
+            mnemonic.to_string().as_bytes(),
+            &passphrase,
+            self.kdf.params(KdfParams::default()),
+        )?
+        .write(&self.secret_file)?;
+        canary.write(&self.secret_file)?;
+        // Nobody has a wallet under the new seed, so every ID gets the latest scheme
+        SchemeTable::fresh().write(&self.secret_file)?;
+        tracing::info!(secret_file = self.secret_file, "Secret created");
+
+        println!(
+            "Canary: twitter ID {} derives to {} under {}",
+            self.id,
+            keygen
+                .derive_with_scheme(DerivationScheme::V1, self.id)?
+                .pubkey(),
+            DerivationScheme::V1
+        );
+        println!("Mnemonic, write it down offline, it will not be shown again:");
+        println!("{}", zeroize::Zeroizing::new(mnemonic.to_string()).as_str());
+        Ok(())
+    }
+}
+
+impl KeyArgs {
+    fn run(&self, passphrase: &PassphraseSource) -> Result<(), Box<dyn std::error::Error>> {
+        let passphrase = passphrase.read("Enter passphrase: ")?;
+        let keygen = KeyGen::load(&self.secret_file, &passphrase)?;
+        match self.chain {
+            Chain::Solana => {
+                let (scheme, key) = keygen.derive(self.id)?;
+                tracing::info!(?key, %scheme, "Key Generated");
+            }
+            Chain::Evm => {
+                let address = keygen.derive_evm(self.id, 0)?;
+                tracing::info!(%address, chain = %self.chain, "Key Generated");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DeriveArgs {
+    fn run(&self, passphrase: &PassphraseSource) -> Result<(), Box<dyn std::error::Error>> {
+        let input = read_input(
+            self.handles_file
+                .as_ref()
+                .or(self.ids_file.as_ref())
+                .map_or("-", String::as_str),
+        )?;
+        let lines = input
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+
+        let passphrase = passphrase.read("Enter passphrase: ")?;
+        let keygen = KeyGen::load(&self.secret_file, &passphrase)?;
+
+        let count = if self.handles_file.is_some() {
+            let handles = lines.map(|(_, line)| line.to_string()).collect::<Vec<_>>();
+            let derived = keygen.derive_handles(&handles)?;
+            write_records(self.format, &derived)?;
+            derived.len()
+        } else {
+            let ids = parse_ids(lines)?;
+            let derived = keygen.derive_ids(&ids)?;
+            write_records(self.format, &derived)?;
+            derived.len()
+        };
+
+        tracing::info!(count, "Wallets derived");
+        Ok(())
+    }
+}
+
+impl ExportArgs {
+    fn run(&self, passphrase: &PassphraseSource) -> Result<(), Box<dyn std::error::Error>> {
+        refuse_overwrite(&self.out)?;
+        let passphrase = passphrase.read("Enter passphrase: ")?;
+        let keygen = KeyGen::load(&self.secret_file, &passphrase)?;
+        let (scheme, keypair) = keygen.derive(self.id)?;
+
+        if let Some(record) = Exports::load([self.secret_file.as_str()])?.get(self.id) {
+            tracing::warn!(exported_at = record.exported_at, "Key was already exported");
+        }
+        eprintln!(
+            "Exporting the private key of {} (twitter ID {}) hands control of the wallet to whoever holds {}",
+            keypair.pubkey(),
+            self.id,
+            self.out
+        );
+        confirm("Type the twitter ID to confirm: ", &self.id.to_string())?;
+        confirm("Type EXPORT to confirm: ", "EXPORT")?;
+
+        let key = zeroize::Zeroizing::new(match self.format {
+            KeyFormat::Json => {
+                serde_json::to_string(zeroize::Zeroizing::new(keypair.to_bytes()).as_slice())?
+            }
+            KeyFormat::Base58 => keypair.to_base58_string(),
+        });
+
+        // Record the export before the key leaves, so it is never handed out unrecorded
+        Exports::append(
+            &self.secret_file,
+            &ExportRecord {
+                twitter_id: self.id,
+                pubkey: keypair.pubkey().to_string(),
+                scheme: scheme.version(),
+                format: format!("{:?}", self.format).to_lowercase(),
+                exported_at: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)?
+                    .as_secs(),
+            },
+        )?;
+        write_private(&self.out, key.as_bytes())?;
+        tracing::warn!(
+            twitter_id = self.id,
+            pubkey = %keypair.pubkey(),
+            out = self.out,
+            "Key exported, it may now be controlled elsewhere"
+        );
+        Ok(())
+    }
+}
+
+impl VerifyArgs {
+    fn run(&self, passphrase: &PassphraseSource) -> Result<(), Box<dyn std::error::Error>> {
+        let passphrase = passphrase.read("Enter passphrase: ")?;
         let keygen = KeyGen::load(&self.secret_file, &passphrase)?;
-        let key = keygen.generate_key(self.id)?;
-        tracing::info!(?key, "Key Generated");
+
+        let (subject, keypair) = match (self.id, &self.handle) {
+            (Some(id), _) => (format!("twitter ID {}", id), keygen.generate_key(id)?),
+            (None, Some(handle)) => (
+                format!("twitter handle {}", handle),
+                keygen.generate_key(handle.as_str())?,
+            ),
+            (None, None) => unreachable!("clap requires an ID or a handle"),
+        };
+        let matches = keypair.pubkey() == self.pubkey;
+
+        let mut result = serde_json::json!({
+            "subject": subject,
+            "pubkey": self.pubkey.to_string(),
+            "matches": matches,
+        });
+        if matches && self.attest {
+            let issued_at = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs();
+            result["attestation"] =
+                serde_json::to_value(Attestation::sign(&keypair, &subject, issued_at)?)?;
+        }
+        println!("{}", serde_json::to_string_pretty(&result)?);
+
+        if !matches {
+            return Err(format!("{} does not derive to {}", subject, self.pubkey).into());
+        }
         Ok(())
     }
 }
+
+impl CanaryArgs {
+    fn run(&self, passphrase: &PassphraseSource) -> Result<(), Box<dyn std::error::Error>> {
+        // A mistyped passphrase would pin the canary to the wrong seed
+        let passphrase = passphrase.read_new()?;
+        let canary = Canary::create(&self.secret_file, &passphrase, self.id)?;
+        tracing::info!(?canary, "Canary created");
+        Ok(())
+    }
+}
+
+impl ReportArgs {
+    fn run(&self, passphrase: &PassphraseSource) -> Result<(), Box<dyn std::error::Error>> {
+        let lines = std::fs::read_to_string(
+            self.ids_file
+                .as_ref()
+                .or(self.handles_file.as_ref())
+                .expect("clap requires one of the files"),
+        )?;
+        let lines = lines.lines().map(str::trim).filter(|line| !line.is_empty());
+
+        let passphrase = passphrase.read("Enter passphrase: ")?;
+        let keygen = KeyGen::load(&self.secret_file, &passphrase)?;
+
+        let (schemes, total, moved) = if self.ids_file.is_some() {
+            let ids = lines.map(str::parse).collect::<Result<Vec<u64>, _>>()?;
+            let (schemes, report) = keygen.migration_report(ids)?;
+            for entry in &report {
+                println!("{}", serde_json::to_string(entry)?);
+            }
+            let moved = report.iter().filter(|e| e.moved()).count();
+            (schemes, report.len(), moved)
+        } else {
+            let (schemes, report) = keygen.handle_migration_report(lines)?;
+            for entry in &report {
+                println!("{}", serde_json::to_string(entry)?);
+            }
+            let moved = report.iter().filter(|e| e.moved()).count();
+            (schemes, report.len(), moved)
+        };
+        tracing::info!(total, moved, "Migration report generated");
+
+        if self.write {
+            schemes.write(&self.secret_file)?;
+            tracing::info!(secret_file = self.secret_file, "Scheme table written");
+        } else {
+            tracing::info!("Dry run, pass --write to store the schemes");
+        }
+        Ok(())
+    }
+}
+
+impl RotateArgs {
+    fn run(&self, passphrase: &PassphraseSource) -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read_to_string(&self.ids_file)?;
+        let ids = parse_ids(
+            input
+                .lines()
+                .enumerate()
+                .map(|(index, line)| (index + 1, line.trim()))
+                .filter(|(_, line)| !line.is_empty()),
+        )?;
+
+        let passphrase = passphrase.read("Enter passphrase: ")?;
+        let old = KeyGen::load(&self.secret_file, &passphrase)?;
+        let new_passphrase = PassphraseSource::Prompt
+            .read(&format!("Enter passphrase for {}: ", self.new_secret_file))?;
+        let new = KeyGen::load(&self.new_secret_file, &new_passphrase)?;
+        if old.derive_with_scheme(DerivationScheme::V1, 0)?.pubkey()
+            == new.derive_with_scheme(DerivationScheme::V1, 0)?.pubkey()
+        {
+            return Err("the new secret derives the same wallets as the old one".into());
+        }
+        // Nobody has been served from the new seed, so unless it was migrated
+        // already every ID moves to the latest scheme, where no two share a wallet
+        let new_schemes = if new.schemes().ids_migrated() {
+            None
+        } else {
+            Some(SchemeTable::fresh())
+        };
+        let new = match &new_schemes {
+            Some(schemes) => new.with_schemes(schemes.clone()),
+            None => new,
+        };
+
+        let exports = Exports::load([self.secret_file.as_str()])?;
+        let mut progress = Progress::load(&self.progress_file)?;
+        let sweeper = Sweeper::new(self.rpc_url.clone());
+        let runtime = tokio::runtime::Runtime::new()?;
+
+        // The whole plan is printed before anything is sent
+        let mut plans = Vec::new();
+        let mut done = 0;
+        for &twitter_id in &ids {
+            // Empty sub-wallets are only planned once a funded one follows
+            let mut gap = Vec::new();
+            for wallet_index in 0..=self.max_index.unwrap_or(MAX_WALLET_INDEX) {
+                if progress.is_done(twitter_id, wallet_index) {
+                    done += 1;
+                    continue;
+                }
+                let from = old.derive_indexed(twitter_id, wallet_index)?.1.pubkey();
+                let to = new.derive_indexed(twitter_id, wallet_index)?.1.pubkey();
+                let plan = runtime.block_on(sweeper.plan(from, to))?;
+                if wallet_index > 0 && self.max_index.is_none() && plan.is_empty() {
+                    if gap.len() as u32 + 1 >= self.gap_limit {
+                        break;
+                    }
+                    gap.push((wallet_index, plan));
+                    continue;
+                }
+                for (wallet_index, plan) in gap.drain(..).chain([(wallet_index, plan)]) {
+                    println!(
+                        "{}",
+                        serde_json::to_string(&PlannedSweep {
+                            twitter_id,
+                            wallet_index,
+                            plan: &plan,
+                        })?
+                    );
+                    plans.push((twitter_id, wallet_index, plan));
+                }
+            }
+        }
+        if self.max_index.is_none() {
+            tracing::warn!(
+                gap_limit = self.gap_limit,
+                "Sub-wallets past gap_limit empty ones in a row were not planned, pass --max-index to go further"
+            );
+        }
+        tracing::info!(
+            wallets = plans.len(),
+            funded = plans.iter().filter(|(_, _, plan)| !plan.is_empty()).count(),
+            underfunded = plans.iter().filter(|(_, _, plan)| plan.underfunded).count(),
+            lamports = plans.iter().map(|(_, _, plan)| plan.lamports).sum::<u64>(),
+            done,
+            "Rotation planned"
+        );
+        if !self.execute {
+            tracing::info!("Dry run, pass --execute to send the sweeps");
+            return Ok(());
+        }
+
+        eprintln!(
+            "Sweeping {} wallets of {} to their wallets under {}",
+            plans.len(),
+            self.secret_file,
+            self.new_secret_file
+        );
+        confirm("Type ROTATE to confirm: ", "ROTATE")?;
+        if let Some(schemes) = &new_schemes {
+            schemes.write(&self.new_secret_file)?;
+        }
+
+        let mut failed = 0;
+        for (twitter_id, wallet_index, plan) in plans {
+            // Only main wallets can be exported
+            if wallet_index == 0 {
+                exports.warn_if_exported(twitter_id);
+            }
+            let (status, signatures, error) = if plan.is_empty() {
+                (RotationStatus::Empty, Vec::new(), None)
+            } else {
+                let (scheme, keypair) = old.derive_indexed(twitter_id, wallet_index)?;
+                let wallet = Account::new(
+                    SocialId::Twitter(TwitterId::new(twitter_id)?),
+                    keypair,
+                    scheme.version(),
+                )
+                .with_wallet_index(wallet_index);
+                match runtime.block_on(sweeper.sweep(&wallet, &plan.to)) {
+                    Ok(signatures) => (
+                        RotationStatus::Swept,
+                        signatures.iter().map(ToString::to_string).collect(),
+                        None,
+                    ),
+                    Err(e) => {
+                        tracing::error!(twitter_id, wallet_index, error = %e, "Sweep failed");
+                        failed += 1;
+                        (RotationStatus::Failed, Vec::new(), Some(e.to_string()))
+                    }
+                }
+            };
+            progress.record(
+                &self.progress_file,
+                RotationRecord {
+                    twitter_id,
+                    wallet_index,
+                    from: plan.from.to_string(),
+                    to: plan.to.to_string(),
+                    status,
+                    signatures,
+                    error,
+                    at: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)?
+                        .as_secs(),
+                },
+            )?;
+        }
+
+        if failed > 0 {
+            return Err(format!("{} sweeps failed, run again to retry them", failed).into());
+        }
+        tracing::info!(progress_file = self.progress_file, "Rotation done");
+        Ok(())
+    }
+}
+
+impl KeystoreCommand {
+    fn run(&self, passphrase: &PassphraseSource) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::Create { out, kdf } => {
+                refuse_overwrite(out)?;
+                let phrase = rpassword::prompt_password("Enter mnemonic: ")?;
+                let mnemonic = bip39::Mnemonic::parse(phrase.trim())?;
+                let passphrase = passphrase.read_new()?;
+                Keystore::seal(
+                    mnemonic.to_string().as_bytes(),
+                    &passphrase,
+                    kdf.params(KdfParams::default()),
+                )?
+                .write(out)?;
+                tracing::info!(out, "Keystore created");
+            }
+            Self::Reencrypt { keystore, kdf } => {
+                let current = Keystore::read(keystore)?;
+                let passphrase = passphrase.read("Enter passphrase: ")?;
+                let secret = current.open(&passphrase)?;
+                Keystore::seal(&secret, &passphrase, kdf.params(current.params()))?
+                    .write(keystore)?;
+                tracing::info!(keystore, "Keystore re-encrypted");
+            }
+            Self::Migrate {
+                secret_file,
+                out,
+                kdf,
+            } => {
+                refuse_overwrite(out)?;
+                let contents = zeroize::Zeroizing::new(std::fs::read_to_string(secret_file)?);
+                if Keystore::parse(&contents)?.is_some() {
+                    return Err(format!("{} is already a keystore", secret_file).into());
+                }
+                let mnemonic = bip39::Mnemonic::parse(contents.trim())?;
+                let passphrase = passphrase.read_new()?;
+                Keystore::seal(
+                    mnemonic.to_string().as_bytes(),
+                    &passphrase,
+                    kdf.params(KdfParams::default()),
+                )?
+                .write(out)?;
+                tracing::info!(
+                    out,
+                    "Keystore created, securely delete {} once verified",
+                    secret_file
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SharesCommand {
+    fn run(&self, passphrase: &PassphraseSource) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::Split {
+                secret_file,
+                threshold,
+                shares,
+                out_dir,
+                kdf,
+            } => {
+                let paths = (1..=*shares)
+                    .map(|index| format!("{}/share-{}.json", out_dir, index))
+                    .collect::<Vec<_>>();
+                for path in &paths {
+                    refuse_overwrite(path)?;
+                }
+
+                let passphrase = passphrase.read("Enter passphrase: ")?;
+                let share_passphrases = (1..=*shares)
+                    .map(|index| prompt_new_share_passphrase(&format!("share {}", index)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let keystores = shares::split(
+                    secret_file,
+                    &passphrase,
+                    *threshold,
+                    &share_passphrases,
+                    kdf.params(KdfParams::default()),
+                )?;
+
+                std::fs::create_dir_all(out_dir)?;
+                let canary = Canary::read(secret_file)?;
+                let schemes = SchemeTable::read(secret_file)?;
+                for (keystore, path) in keystores.iter().zip(&paths) {
+                    keystore.write(path)?;
+                    if let Some(canary) = &canary {
+                        canary.write(path)?;
+                    }
+                    if let Some(schemes) = &schemes {
+                        schemes.write(path)?;
+                    }
+                }
+                tracing::info!(
+                    out_dir,
+                    threshold,
+                    shares,
+                    "Shares created, hand one to each holder"
+                );
+            }
+            Self::Combine {
+                share_file,
+                out,
+                kdf,
+            } => {
+                refuse_overwrite(out)?;
+                refuse_overwrite(&SchemeTable::path_for(out))?;
+                let schemes = SchemeTable::load(share_file.iter().map(String::as_str))?;
+                let keystores = share_file
+                    .iter()
+                    .map(|path| {
+                        let keystore = Keystore::read(path)?;
+                        let passphrase = PassphraseSource::Prompt
+                            .read(&format!("Enter passphrase for {}: ", path))?;
+                        Ok((keystore, passphrase))
+                    })
+                    .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+                let mnemonic = shares::combine(&keystores)?;
+
+                let passphrase = passphrase.read_new()?;
+                KeyGen::from(mnemonic.to_seed(passphrase.as_str()))
+                    .verify_canaries(share_file.iter().map(String::as_str))?;
+                Keystore::seal(
+                    mnemonic.to_string().as_bytes(),
+                    &passphrase,
+                    kdf.params(KdfParams::default()),
+                )?
+                .write(out)?;
+                // Without it every migrated ID would be served its V1 wallet again
+                if schemes != SchemeTable::default() {
+                    schemes.write(out)?;
+                }
+                tracing::info!(out, "Keystore recombined from shares");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Share passphrases only unlock their share, they never touch the seed.
+fn prompt_new_share_passphrase(
+    share: &str,
+) -> Result<zeroize::Zeroizing<String>, Box<dyn std::error::Error>> {
+    let passphrase = zeroize::Zeroizing::new(rpassword::prompt_password(format!(
+        "Enter passphrase for {}: ",
+        share
+    ))?);
+    let confirm = zeroize::Zeroizing::new(rpassword::prompt_password(format!(
+        "Confirm passphrase for {}: ",
+        share
+    ))?);
+    if passphrase != confirm {
+        return Err("passphrases do not match".into());
+    }
+    Ok(passphrase)
+}
+
+/// Parse twitter IDs from numbered, non-empty lines.
+fn parse_ids<'a>(
+    lines: impl Iterator<Item = (usize, &'a str)>,
+) -> Result<Vec<u64>, Box<dyn std::error::Error>> {
+    Ok(lines
+        .map(|(number, line)| {
+            line.parse::<u64>()
+                .map_err(|e| format!("line {}: invalid twitter ID {:?}: {}", number, line, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Read a whole file, or stdin for `-`.
+fn read_input(path: &str) -> Result<String, std::io::Error> {
+    if path == "-" {
+        std::io::read_to_string(std::io::stdin())
+    } else {
+        std::fs::read_to_string(path)
+    }
+}
+
+fn write_records<T: serde::Serialize>(
+    format: Format,
+    records: &[T],
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write as _;
+
+    let stdout = std::io::stdout().lock();
+    match format {
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(stdout);
+            for record in records {
+                writer.serialize(record)?;
+            }
+            writer.flush()?;
+        }
+        Format::Json => {
+            let mut writer = std::io::BufWriter::new(stdout);
+            for record in records {
+                serde_json::to_writer(&mut writer, record)?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Ask on the terminal and fail unless the answer is `expected`.
+fn confirm(prompt: &str, expected: &str) -> Result<(), Box<dyn std::error::Error>> {
+    eprint!("{}", prompt);
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if answer.trim() != expected {
+        return Err("confirmation failed, nothing was done".into());
+    }
+    Ok(())
+}
+
+/// Create `path` readable only by the current user, failing if it exists.
+fn write_private(path: &str, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write as _;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+fn refuse_overwrite(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if std::path::Path::new(path).exists() {
+        return Err(format!("refusing to overwrite existing file: {}", path).into());
+    }
+    Ok(())
+}
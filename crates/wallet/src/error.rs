@@ -0,0 +1,53 @@
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Keystore error: {0}")]
+    Keystore(String),
+
+    #[error("Canary mismatch: wrong passphrase or secret file")]
+    CanaryMismatch,
+
+    #[error("Unknown derivation scheme: v{0}")]
+    UnknownScheme(u8),
+
+    #[error("Invalid handle: {0:?}")]
+    InvalidHandle(String),
+
+    #[error("Invalid EVM address: {0:?}")]
+    InvalidEvmAddress(String),
+
+    #[error("Scheme table error: {0}")]
+    SchemeTable(String),
+
+    #[error("Wallet index {index} is not derivable under {scheme}")]
+    UnsupportedWalletIndex { index: u32, scheme: String },
+
+    #[error("{platform} IDs are not derivable under {scheme}")]
+    UnsupportedPlatform { platform: String, scheme: String },
+
+    #[error("secp256k1 error: {0}")]
+    Secp256k1(String),
+
+    #[error("Secret sharing error: {0}")]
+    Shamir(String),
+
+    #[error("Passphrase error: {0}")]
+    Passphrase(String),
+
+    #[error("Invalid attestation: {0}")]
+    Attestation(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Mnemonic error: {0}")]
+    Mnemonic(#[from] bip39::Error),
+}
+
+impl From<libsecp256k1::Error> for Error {
+    fn from(e: libsecp256k1::Error) -> Self {
+        Self::Secp256k1(e.to_string())
+    }
+}
@@ -0,0 +1,228 @@
+//! secp256k1 wallets for EVM chains, derived from the master seed with BIP32.
+//!
+//! A twitter ID's address is at
+//! `m/44'/60'/{id >> 62}'/{(id >> 31) & 0x7fffffff}'/{id & 0x7fffffff}'`,
+//! the layout of [`DerivationScheme::V2`](crate::scheme::DerivationScheme::V2)
+//! under the Ethereum coin type. Sub-wallets append `/{index}'` as on Solana.
+
+use hmac::{Hmac, Mac as _};
+use libsecp256k1::{PublicKey, SecretKey};
+use sha3::{Digest as _, Keccak256};
+use zeroize::Zeroizing;
+
+use crate::{error::Error, scheme::MAX_WALLET_INDEX};
+
+const HARDENED: u32 = 0x8000_0000;
+const INDEX_MASK: u64 = 0x7fff_ffff;
+/// BIP44 coin type of Ethereum, shared by EVM chains such as Base
+const COIN_TYPE: u32 = 60;
+
+/// A 20 byte EVM address, displayed with its EIP-55 checksum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EvmAddress([u8; 20]);
+
+impl EvmAddress {
+    /// The last 20 bytes of the Keccak-256 of the uncompressed public key.
+    pub fn from_public_key(public_key: &PublicKey) -> Self {
+        let hash = Keccak256::digest(&public_key.serialize()[1..]);
+        Self(hash[12..].try_into().expect("Keccak-256 is 32 bytes"))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for EvmAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hex = hex::encode(self.0);
+        let hash = Keccak256::digest(hex.as_bytes());
+        f.write_str("0x")?;
+        for (i, c) in hex.chars().enumerate() {
+            let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0xf;
+            if nibble >= 8 {
+                write!(f, "{}", c.to_ascii_uppercase())?;
+            } else {
+                write!(f, "{}", c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses hex with or without `0x`. Mixed case addresses must carry a valid
+/// EIP-55 checksum, all lowercase or all uppercase ones have none.
+impl std::str::FromStr for EvmAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidEvmAddress(s.to_string());
+        let digits = s.strip_prefix("0x").unwrap_or(s);
+        let bytes = hex::decode(digits).map_err(|_| invalid())?;
+        let address = Self(bytes.try_into().map_err(|_| invalid())?);
+
+        let mixed_case = digits.chars().any(|c| c.is_ascii_lowercase())
+            && digits.chars().any(|c| c.is_ascii_uppercase());
+        if mixed_case && address.to_string()[2..] != *digits {
+            return Err(invalid());
+        }
+        Ok(address)
+    }
+}
+
+impl serde::Serialize for EvmAddress {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// BIP32 path of sub-wallet `index` of a twitter ID, index 0 is its main wallet.
+pub fn path_for(id: u64, index: u32) -> Result<Vec<u32>, Error> {
+    let mut path = vec![
+        44 | HARDENED,
+        COIN_TYPE | HARDENED,
+        (id >> 62) as u32 | HARDENED,
+        ((id >> 31) & INDEX_MASK) as u32 | HARDENED,
+        (id & INDEX_MASK) as u32 | HARDENED,
+    ];
+    match index {
+        0 => {}
+        1..=MAX_WALLET_INDEX => path.push(index | HARDENED),
+        _ => {
+            return Err(Error::UnsupportedWalletIndex {
+                index,
+                scheme: "evm".to_string(),
+            })
+        }
+    }
+    Ok(path)
+}
+
+/// BIP32 private key derivation from `seed` along `path`, indices with the
+/// top bit set are hardened.
+///
+/// The invalid keys BIP32 skips over are reported as errors instead, the
+/// chance of hitting one is below 2^-127.
+pub(crate) fn derive_secret(seed: &[u8], path: &[u32]) -> Result<SecretKey, Error> {
+    let mut i = hmac_sha512(b"Bitcoin seed", &[seed]);
+    let mut key = SecretKey::parse_slice(&i[..32])?;
+    let mut chain_code = Zeroizing::new([0u8; 32]);
+    chain_code.copy_from_slice(&i[32..]);
+
+    for &index in path {
+        i = if index & HARDENED != 0 {
+            let parent = Zeroizing::new(key.serialize());
+            hmac_sha512(
+                chain_code.as_slice(),
+                &[&[0], parent.as_slice(), &index.to_be_bytes()],
+            )
+        } else {
+            hmac_sha512(
+                chain_code.as_slice(),
+                &[
+                    &PublicKey::from_secret_key(&key).serialize_compressed(),
+                    &index.to_be_bytes(),
+                ],
+            )
+        };
+        key.tweak_add_assign(&SecretKey::parse_slice(&i[..32])?)?;
+        chain_code.copy_from_slice(&i[32..]);
+    }
+    Ok(key)
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> Zeroizing<[u8; 64]> {
+    let mut mac = Hmac::<sha2::Sha512>::new_from_slice(key).expect("HMAC takes keys of any length");
+    for part in data {
+        mac.update(part);
+    }
+    Zeroizing::new(mac.finalize().into_bytes().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const H: u32 = HARDENED;
+
+    fn address(seed: &[u8], path: &[u32]) -> String {
+        EvmAddress::from_public_key(&PublicKey::from_secret_key(
+            &derive_secret(seed, path).unwrap(),
+        ))
+        .to_string()
+    }
+
+    #[test]
+    fn test_address() {
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        let key = SecretKey::parse(&one).unwrap();
+        assert_eq!(
+            EvmAddress::from_public_key(&PublicKey::from_secret_key(&key)).to_string(),
+            "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"
+        );
+    }
+
+    #[test]
+    fn test_parse_address() {
+        const ADDRESS: &str = "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf";
+        let address = ADDRESS.parse::<EvmAddress>().unwrap();
+        assert_eq!(address.to_string(), ADDRESS);
+        assert_eq!(
+            ADDRESS[2..].to_lowercase().parse::<EvmAddress>().unwrap(),
+            address
+        );
+        // Bad checksum
+        assert!("0x7e5F4552091A69125d5DfCb7b8C2659029395Bdf"
+            .parse::<EvmAddress>()
+            .is_err());
+        assert!("0x7E5F4552091A69125d5DfCb7b8C2659029395B"
+            .parse::<EvmAddress>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_bip32_vectors() {
+        // Test vector 1 of BIP32
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        assert_eq!(
+            hex::encode(derive_secret(&seed, &[]).unwrap().serialize()),
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+        );
+        assert_eq!(
+            hex::encode(
+                derive_secret(&seed, &[H, 1, 2 | H, 2, 1_000_000_000])
+                    .unwrap()
+                    .serialize()
+            ),
+            "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8"
+        );
+
+        // The first accounts of the well known Hardhat and Anvil mnemonic
+        let seed =
+            bip39::Mnemonic::parse("test test test test test test test test test test test junk")
+                .unwrap()
+                .to_seed("");
+        assert_eq!(
+            address(&seed, &[44 | H, 60 | H, H, 0, 0]),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+        assert_eq!(
+            address(&seed, &[44 | H, 60 | H, H, 0, 1]),
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+        );
+    }
+
+    #[test]
+    fn test_path_for() {
+        assert_eq!(
+            path_for(0x8000_0000, 0).unwrap(),
+            [44 | H, 60 | H, H, 1 | H, H]
+        );
+        assert_eq!(
+            path_for(u64::MAX, 2).unwrap(),
+            [44 | H, 60 | H, 3 | H, u32::MAX, u32::MAX, 2 | H]
+        );
+        assert!(path_for(0, MAX_WALLET_INDEX + 1).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+//! Audit log of private keys handed out with `x-link-wallet export`, stored as
+//! JSON lines next to the secret file. Once exported, a key may be controlled
+//! by someone else, so anything signing with it warns.
+
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Error, jsonl};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportRecord {
+    pub twitter_id: u64,
+    pub pubkey: String,
+    /// Version of the scheme that derived the key
+    pub scheme: u8,
+    /// Format the key was written in
+    pub format: String,
+    /// Unix time of the export
+    pub exported_at: u64,
+}
+
+/// Every exported key, by twitter ID. Later exports of the same ID replace
+/// earlier ones.
+///
+/// The logs are read again whenever one of them changes, so a key exported
+/// while a server runs is seen on its next lookup.
+#[derive(Debug, Default)]
+pub struct Exports {
+    logs: Vec<String>,
+    state: RwLock<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Length and modification time of each log when it was read, `None`
+    /// if it was missing
+    stamps: Vec<Option<(u64, SystemTime)>>,
+    records: BTreeMap<u64, ExportRecord>,
+}
+
+impl Exports {
+    pub fn path_for(secret_file: &str) -> String {
+        format!("{}.exports", secret_file)
+    }
+
+    /// Load the logs next to each of `files`, missing logs count as empty.
+    pub fn load<'a>(files: impl IntoIterator<Item = &'a str>) -> Result<Self, Error> {
+        let logs: Vec<_> = files.into_iter().map(Self::path_for).collect();
+        let state = Self::read(&logs)?;
+        Ok(Self {
+            logs,
+            state: RwLock::new(state),
+        })
+    }
+
+    fn read(logs: &[String]) -> Result<State, Error> {
+        let mut state = State {
+            stamps: logs.iter().map(|log| stamp(log)).collect(),
+            records: BTreeMap::new(),
+        };
+        for log in logs {
+            for record in jsonl::read::<ExportRecord>(log)? {
+                state.records.insert(record.twitter_id, record);
+            }
+        }
+        Ok(state)
+    }
+
+    /// Read the logs again if any changed, keeping the current records if
+    /// they can't be read.
+    fn refresh(&self) {
+        let stamps: Vec<_> = self.logs.iter().map(|log| stamp(log)).collect();
+        if self.state.read().unwrap_or_else(|e| e.into_inner()).stamps == stamps {
+            return;
+        }
+        match Self::read(&self.logs) {
+            Ok(state) => {
+                tracing::info!(count = state.records.len(), "reloaded exported keys");
+                *self.state.write().unwrap_or_else(|e| e.into_inner()) = state;
+            }
+            Err(e) => tracing::error!(error = %e, "keeping the current exported keys"),
+        }
+    }
+
+    /// Append `record` to the log next to `secret_file`, creating it if needed.
+    pub fn append(secret_file: &str, record: &ExportRecord) -> Result<(), Error> {
+        jsonl::append(&Self::path_for(secret_file), record)
+    }
+
+    pub fn get(&self, twitter_id: u64) -> Option<ExportRecord> {
+        self.refresh();
+        self.state
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .records
+            .get(&twitter_id)
+            .cloned()
+    }
+
+    /// Warn if the twitter ID's key has been exported.
+    pub fn warn_if_exported(&self, twitter_id: u64) {
+        if let Some(record) = self.get(twitter_id) {
+            tracing::warn!(
+                twitter_id,
+                pubkey = record.pubkey,
+                exported_at = record.exported_at,
+                "key was exported, it may be controlled elsewhere"
+            );
+        }
+    }
+}
+
+fn stamp(log: &str) -> Option<(u64, SystemTime)> {
+    let metadata = std::fs::metadata(log).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(twitter_id: u64, exported_at: u64) -> ExportRecord {
+        ExportRecord {
+            twitter_id,
+            pubkey: format!("pubkey-{}", twitter_id),
+            scheme: 1,
+            format: "json".to_string(),
+            exported_at,
+        }
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let secret_file = std::env::temp_dir()
+            .join(format!("x-link-exports-{}", std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        let exports = Exports::load([secret_file.as_str()]).unwrap();
+        assert_eq!(exports.get(1), None);
+
+        Exports::append(&secret_file, &record(1, 10)).unwrap();
+        Exports::append(&secret_file, &record(2, 20)).unwrap();
+        Exports::append(&secret_file, &record(1, 30)).unwrap();
+
+        let loaded = Exports::load([secret_file.as_str()]).unwrap();
+        assert_eq!(loaded.get(1), Some(record(1, 30)));
+        assert_eq!(loaded.get(2), Some(record(2, 20)));
+        assert_eq!(loaded.get(3), None);
+
+        // Exports made after loading are seen too
+        assert_eq!(exports.get(2), Some(record(2, 20)));
+        Exports::append(&secret_file, &record(3, 40)).unwrap();
+        assert_eq!(exports.get(3), Some(record(3, 40)));
+
+        std::fs::remove_file(Exports::path_for(&secret_file)).unwrap();
+    }
+}
@@ -0,0 +1,64 @@
+//! Handle normalization and the HKDF construction used by handle-based
+//! wallets from [`DerivationScheme::V3`](crate::scheme::DerivationScheme::V3).
+
+use hkdf::Hkdf;
+use unicode_normalization::UnicodeNormalization as _;
+use zeroize::Zeroizing;
+
+use crate::error::Error;
+
+/// HKDF salt, fixed so the extract step is domain separated from any other
+/// use of the master seed.
+const SALT: &[u8] = b"x-link/handle/salt/v3";
+/// Prefix of the HKDF info, followed by the normalized handle.
+const INFO: &[u8] = b"x-link/handle/ed25519/v3:";
+
+/// Normalize a handle so every spelling of it derives the same wallet:
+/// surrounding whitespace and a single leading '@' are dropped, then the
+/// handle is NFKC normalized and lowercased.
+pub fn normalize(handle: &str) -> Result<String, Error> {
+    let handle = handle.trim();
+    let handle = handle.strip_prefix('@').unwrap_or(handle);
+    let normalized = handle.nfkc().collect::<String>().to_lowercase();
+
+    if normalized.is_empty() || normalized.chars().any(char::is_whitespace) {
+        return Err(Error::InvalidHandle(handle.to_string()));
+    }
+    Ok(normalized)
+}
+
+/// HKDF-SHA512 over the master seed, expanded into an ed25519 seed for
+/// an already normalized handle.
+pub(crate) fn seed_for_handle(secret: &[u8; 64], normalized: &str) -> Zeroizing<[u8; 32]> {
+    let mut seed = Zeroizing::new([0u8; 32]);
+    Hkdf::<sha2::Sha512>::new(Some(SALT), secret)
+        .expand_multi_info(&[INFO, normalized.as_bytes()], seed.as_mut_slice())
+        .expect("32 bytes is a valid HKDF-SHA512 output length");
+    seed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("elon").unwrap(), "elon");
+        assert_eq!(normalize("Elon").unwrap(), "elon");
+        assert_eq!(normalize("@ELON").unwrap(), "elon");
+        assert_eq!(normalize("  @elon \n").unwrap(), "elon");
+        // Fullwidth letters fold to ASCII under NFKC
+        assert_eq!(normalize("ＥＬＯＮ").unwrap(), "elon");
+        // Composed and decomposed forms are the same handle
+        assert_eq!(
+            normalize("caf\u{e9}").unwrap(),
+            normalize("cafe\u{301}").unwrap()
+        );
+        // Only one '@' is stripped
+        assert_eq!(normalize("@@elon").unwrap(), "@elon");
+
+        assert!(normalize("").is_err());
+        assert!(normalize("@").is_err());
+        assert!(normalize("el on").is_err());
+    }
+}
@@ -0,0 +1,108 @@
+//! Append-only logs of one JSON record per line.
+
+use std::io::{BufRead as _, Read as _, Seek as _, Write as _};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::Error;
+
+/// Every record in the log at `path`, oldest first. A missing log is empty.
+///
+/// A line cut short by a crash mid-[`append`] is skipped with a warning, as
+/// is any unreadable last line, so the log still loads.
+pub(crate) fn read<T: DeserializeOwned>(path: &str) -> Result<Vec<T>, Error> {
+    let log = match std::fs::File::open(path) {
+        Ok(log) => log,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let lines = std::io::BufReader::new(log)
+        .lines()
+        .collect::<Result<Vec<_>, _>>()?;
+    let last = lines.iter().rposition(|line| !line.trim().is_empty());
+
+    let mut records = Vec::new();
+    for (number, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(record) => records.push(record),
+            Err(e) if e.is_eof() || Some(number) == last => {
+                tracing::warn!(path, line = number + 1, error = %e, "Skipping torn log line");
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(records)
+}
+
+/// Append `record` to the log at `path`, creating it readable only by the
+/// current user. Synced before returning.
+///
+/// The record starts on a line of its own even if the last append was cut
+/// short.
+pub(crate) fn append<T: Serialize>(path: &str, record: &T) -> Result<(), Error> {
+    let mut options = std::fs::OpenOptions::new();
+    options.read(true).append(true).create(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut log = options.open(path)?;
+    let mut line = Vec::new();
+    if log.metadata()?.len() > 0 {
+        let mut last = [0];
+        log.seek(std::io::SeekFrom::End(-1))?;
+        log.read_exact(&mut last)?;
+        if last[0] != b'\n' {
+            line.push(b'\n');
+        }
+    }
+    serde_json::to_writer(&mut line, record)?;
+    line.push(b'\n');
+    log.write_all(&line)?;
+    log.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_torn_line() {
+        let path = std::env::temp_dir()
+            .join(format!("x-link-jsonl-{}", std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        append(&path, &json!({ "a": 1 })).unwrap();
+        // Crashed mid-append
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(br#"{"a":"#)
+            .unwrap();
+        assert_eq!(
+            read::<serde_json::Value>(&path).unwrap(),
+            [json!({ "a": 1 })]
+        );
+
+        // The next append isn't glued onto the torn line
+        append(&path, &json!({ "a": 2 })).unwrap();
+        assert_eq!(
+            read::<serde_json::Value>(&path).unwrap(),
+            [json!({ "a": 1 }), json!({ "a": 2 })]
+        );
+
+        // Other unreadable lines still fail the log
+        append(&path, &json!("not an object")).unwrap();
+        append(&path, &json!({ "a": 3 })).unwrap();
+        assert!(read::<serde_json::Map<String, serde_json::Value>>(&path).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,19 +1,92 @@
 use sha2::Digest;
-use solana_sdk::{derivation_path::DerivationPath, signature::Keypair, signer::SeedDerivable as _};
+use solana_sdk::{signature::Keypair, signer::SeedDerivable as _};
+use x_link_types::social::SocialId;
+use x_link_utils::locked::Locked;
+use zeroize::{Zeroize as _, Zeroizing};
 
-pub struct KeyGen([u8; 64]);
+use crate::{
+    canary::Canary,
+    error::Error,
+    evm::{self, EvmAddress},
+    handle,
+    keystore::Keystore,
+    passphrase::PassphraseSource,
+    scheme::DerivationScheme,
+    scheme_table::SchemeTable,
+    social,
+};
 
-impl std::ops::Deref for KeyGen {
-    type Target = [u8; 64];
+/// The master seed, kept in locked memory and wiped on drop, along with the
+/// [`SchemeTable`] saying which scheme serves each ID.
+pub struct KeyGen {
+    seed: Locked<[u8; 64]>,
+    schemes: SchemeTable,
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+impl From<[u8; 64]> for KeyGen {
+    /// Copy the seed into locked memory and wipe the array passed in. Nothing
+    /// is migrated, see [`Self::with_schemes`].
+    fn from(mut value: [u8; 64]) -> Self {
+        let mut seed = Locked::new([0u8; 64]);
+        seed.as_mut().copy_from_slice(&value);
+        value.zeroize();
+        Self {
+            seed,
+            schemes: SchemeTable::default(),
+        }
     }
 }
 
-impl From<[u8; 64]> for KeyGen {
-    fn from(value: [u8; 64]) -> Self {
-        Self(value)
+/// Where the master seed is loaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretSource {
+    /// A keystore or legacy plaintext mnemonic, see [`KeyGen::load`]
+    File(String),
+    /// Share files from `x-link-wallet shares split`, each with where its
+    /// holder's passphrase is read from, see [`KeyGen::load_shares`]
+    Shares(Vec<(String, PassphraseSource)>),
+}
+
+impl SecretSource {
+    /// The files the seed is loaded from, canaries and export logs live next to them.
+    pub fn files(&self) -> Vec<&str> {
+        match self {
+            Self::File(secret_file) => vec![secret_file.as_str()],
+            Self::Shares(shares) => shares.iter().map(|(file, _)| file.as_str()).collect(),
+        }
+    }
+
+    /// Load the seed, reading its passphrase from `passphrase`. Each share
+    /// holder's passphrase is read first, from the share's own source.
+    pub fn load(
+        &self,
+        passphrase: &PassphraseSource,
+    ) -> Result<KeyGen, Box<dyn std::error::Error>> {
+        match self {
+            Self::File(secret_file) => {
+                KeyGen::load(secret_file, &passphrase.read("Enter passphrase: ")?)
+            }
+            Self::Shares(shares) => {
+                let shares = shares
+                    .iter()
+                    .map(|(share_file, source)| {
+                        let share_passphrase = source
+                            .read(&format!("Enter passphrase for {}: ", share_file))
+                            .map_err(|e| match source {
+                                PassphraseSource::Prompt => Error::Passphrase(format!(
+                                    "cannot prompt for the passphrase of {}, pass \
+                                     --share-passphrase-file or --share-passphrase-fd \
+                                     once per --share-file",
+                                    share_file
+                                )),
+                                _ => e,
+                            })?;
+                        Ok((share_file.clone(), share_passphrase))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                KeyGen::load_shares(&shares, &passphrase.read("Enter passphrase: ")?)
+            }
+        }
     }
 }
 
@@ -23,43 +96,262 @@ pub trait KeyGenerator<T> {
 
 impl KeyGenerator<u64> for KeyGen {
     fn generate_key(&self, id: u64) -> Result<Keypair, Box<dyn std::error::Error>> {
-        Self::key_from_id_inner(**self, id)
+        self.derive(id).map(|(_, keypair)| keypair)
     }
 }
 
 impl KeyGenerator<&str> for KeyGen {
     fn generate_key(&self, handle: &str) -> Result<Keypair, Box<dyn std::error::Error>> {
-        Self::key_from_handle_inner(**self, handle)
+        self.derive_handle(handle).map(|(_, keypair)| keypair)
+    }
+}
+
+impl KeyGenerator<SocialId> for KeyGen {
+    fn generate_key(&self, id: SocialId) -> Result<Keypair, Box<dyn std::error::Error>> {
+        self.derive_social(id).map(|(_, keypair)| keypair)
     }
 }
 
 impl KeyGen {
+    /// Serve IDs under the schemes in `schemes` instead of the ones loaded
+    /// with the seed.
+    pub fn with_schemes(mut self, schemes: SchemeTable) -> Self {
+        self.schemes = schemes;
+        self
+    }
+
+    pub fn schemes(&self) -> &SchemeTable {
+        &self.schemes
+    }
+
+    /// Derive the wallet for a twitter ID along with the scheme that produced it.
+    pub fn derive(
+        &self,
+        id: u64,
+    ) -> Result<(DerivationScheme, Keypair), Box<dyn std::error::Error>> {
+        let scheme = self.schemes.for_id(id);
+        Ok((scheme, self.derive_with_scheme(scheme, id)?))
+    }
+
+    /// Derive the wallet for a twitter ID under a specific scheme, e.g. to
+    /// reach a V1 wallet for an ID that has since moved to V2.
+    pub fn derive_with_scheme(
+        &self,
+        scheme: DerivationScheme,
+        id: u64,
+    ) -> Result<Keypair, Box<dyn std::error::Error>> {
+        Self::key_from_id_inner(self.seed(), scheme, id)
+    }
+
+    /// Derive sub-wallet `index` of a twitter ID along with the scheme that
+    /// produced it. Index 0 is the wallet from [`Self::derive`].
+    pub fn derive_indexed(
+        &self,
+        id: u64,
+        index: u32,
+    ) -> Result<(DerivationScheme, Keypair), Box<dyn std::error::Error>> {
+        let scheme = self.schemes.for_wallet(id, index);
+        Ok((scheme, self.derive_indexed_with_scheme(scheme, id, index)?))
+    }
+
+    /// Derive sub-wallet `index` of a twitter ID under a specific scheme.
+    pub fn derive_indexed_with_scheme(
+        &self,
+        scheme: DerivationScheme,
+        id: u64,
+        index: u32,
+    ) -> Result<Keypair, Box<dyn std::error::Error>> {
+        Keypair::from_seed_and_derivation_path(self.seed(), Some(scheme.wallet_path(id, index)?))
+    }
+
+    /// Derive the wallet for a twitter handle along with the scheme that
+    /// produced it, see [`SchemeTable::for_handle`].
+    pub fn derive_handle(
+        &self,
+        handle: &str,
+    ) -> Result<(DerivationScheme, Keypair), Box<dyn std::error::Error>> {
+        let scheme = self.schemes.for_handle(handle);
+        Ok((scheme, self.derive_handle_with_scheme(scheme, handle)?))
+    }
+
+    /// Derive the wallet for a twitter handle under a specific scheme, e.g. to
+    /// reach the wallet a handle had before V3 normalized it.
+    pub fn derive_handle_with_scheme(
+        &self,
+        scheme: DerivationScheme,
+        handle: &str,
+    ) -> Result<Keypair, Box<dyn std::error::Error>> {
+        Self::key_from_handle_inner(self.seed(), scheme, handle)
+    }
+
+    /// Derive the wallet for an ID on any platform along with the scheme that
+    /// produced it. Twitter IDs get the same wallet as from [`Self::derive`].
+    pub fn derive_social(
+        &self,
+        id: SocialId,
+    ) -> Result<(DerivationScheme, Keypair), Box<dyn std::error::Error>> {
+        let scheme = self.schemes.for_social(&id);
+        Ok((scheme, self.derive_social_with_scheme(scheme, id)?))
+    }
+
+    /// Derive the wallet for an ID on any platform under a specific scheme.
+    pub fn derive_social_with_scheme(
+        &self,
+        scheme: DerivationScheme,
+        id: SocialId,
+    ) -> Result<Keypair, Box<dyn std::error::Error>> {
+        match (id, scheme) {
+            (SocialId::Twitter(id), _) => Self::key_from_id_inner(self.seed(), scheme, id.get()),
+            (_, DerivationScheme::V3) => Keypair::from_seed(
+                social::seed_for_social(self.seed(), id.platform(), id.id()).as_slice(),
+            ),
+            (_, DerivationScheme::V1 | DerivationScheme::V2) => Err(Error::UnsupportedPlatform {
+                platform: id.platform().to_string(),
+                scheme: scheme.to_string(),
+            }
+            .into()),
+        }
+    }
+
+    /// Derive the EVM address of sub-wallet `index` of a twitter ID, see
+    /// [`crate::evm`]. Index 0 is the ID's main wallet.
+    pub fn derive_evm(
+        &self,
+        id: u64,
+        index: u32,
+    ) -> Result<EvmAddress, Box<dyn std::error::Error>> {
+        let secret = evm::derive_secret(self.seed(), &evm::path_for(id, index)?)?;
+        Ok(EvmAddress::from_public_key(
+            &libsecp256k1::PublicKey::from_secret_key(&secret),
+        ))
+    }
+
+    /// Load the master seed from `secret_file`, which is either an encrypted
+    /// [`Keystore`] or a legacy plaintext mnemonic.
+    /// The passphrase unlocks the keystore and salts the BIP39 seed.
+    ///
+    /// If a [`Canary`] is stored next to the secret file, the loaded seed must
+    /// match it, so a mistyped passphrase is rejected here. IDs are served
+    /// under the [`SchemeTable`] stored next to it, if any.
     pub fn load(secret_file: &str, passphrase: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let secret = std::fs::read_to_string(secret_file)?.trim().to_string();
-        let seed = bip39::Mnemonic::parse(secret)?.to_seed(passphrase);
-        Ok(Self::from(seed))
+        let keygen = Self::load_unchecked(secret_file, passphrase)?;
+        keygen.verify_canaries([secret_file])?;
+        Ok(keygen.with_schemes(SchemeTable::load([secret_file])?))
+    }
+
+    /// Load the master seed from Shamir shares, each given as a share file
+    /// and its holder's passphrase. `passphrase` salts the BIP39 seed.
+    ///
+    /// Canaries and scheme tables stored next to the share files are used like
+    /// in [`Self::load`].
+    pub fn load_shares(
+        shares: &[(String, Zeroizing<String>)],
+        passphrase: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let keystores = shares
+            .iter()
+            .map(|(share_file, share_passphrase)| {
+                Ok((Keystore::read(share_file)?, share_passphrase.clone()))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let mnemonic = crate::shares::combine(&keystores)?;
+        let keygen = Self::from(mnemonic.to_seed(passphrase));
+        let share_files = shares.iter().map(|(share_file, _)| share_file.as_str());
+        keygen.verify_canaries(share_files.clone())?;
+        Ok(keygen.with_schemes(SchemeTable::load(share_files)?))
+    }
+
+    /// Check the seed against the canary stored next to each of `files`.
+    /// Warns if none of them has one.
+    pub fn verify_canaries<'a>(
+        &self,
+        files: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let files = files.into_iter().collect::<Vec<_>>();
+        let mut found = false;
+        for file in &files {
+            if let Some(canary) = Canary::read(file)? {
+                canary.verify(self)?;
+                found = true;
+            }
+        }
+        if !found {
+            tracing::warn!(
+                ?files,
+                "no canary found, the passphrase can't be verified. Create one with `x-link-wallet canary`"
+            );
+        }
+        Ok(())
+    }
+
+    pub(crate) fn load_unchecked(
+        secret_file: &str,
+        passphrase: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mnemonic = Self::read_mnemonic(secret_file, passphrase)?;
+        Ok(Self::from(mnemonic.to_seed(passphrase)))
     }
 
-    fn seed_for_handle(secret: [u8; 64], handle: &str) -> [u8; 64] {
+    pub(crate) fn read_mnemonic(
+        secret_file: &str,
+        passphrase: &str,
+    ) -> Result<bip39::Mnemonic, Box<dyn std::error::Error>> {
+        let contents = zeroize::Zeroizing::new(std::fs::read_to_string(secret_file)?);
+        match Keystore::parse(&contents).map_err(|e| format!("{}: {}", secret_file, e))? {
+            Some(keystore) => {
+                if keystore.share().is_some() {
+                    return Err(format!(
+                        "{} is a single share, load it with the rest of its split",
+                        secret_file
+                    )
+                    .into());
+                }
+                let phrase = keystore.open(passphrase)?;
+                Ok(bip39::Mnemonic::parse(std::str::from_utf8(&phrase)?)?)
+            }
+            None => {
+                tracing::warn!(
+                    secret_file,
+                    "loading plaintext mnemonic, migrate it with `x-link-wallet keystore migrate`"
+                );
+                Ok(bip39::Mnemonic::parse(contents.trim())?)
+            }
+        }
+    }
+
+    /// The raw seed, for the crate's own derivations only.
+    pub(crate) fn seed(&self) -> &[u8; 64] {
+        self.seed.as_ref()
+    }
+
+    fn seed_for_handle(secret: &[u8; 64], handle: &str) -> Zeroizing<[u8; 64]> {
         let mut hasher = sha2::Sha512::new();
         hasher.update(secret);
         hasher.update(handle.as_bytes());
-        hasher.finalize().into()
+        Zeroizing::new(hasher.finalize().into())
     }
 
     fn key_from_handle_inner(
-        secret: [u8; 64],
+        secret: &[u8; 64],
+        scheme: DerivationScheme,
         handle: &str,
     ) -> Result<Keypair, Box<dyn std::error::Error>> {
-        Keypair::from_seed(&Self::seed_for_handle(secret, handle))
+        match scheme {
+            DerivationScheme::V1 | DerivationScheme::V2 => {
+                Keypair::from_seed(Self::seed_for_handle(secret, handle).as_slice())
+            }
+            DerivationScheme::V3 => Keypair::from_seed(
+                handle::seed_for_handle(secret, &handle::normalize(handle)?).as_slice(),
+            ),
+        }
     }
 
-    fn key_from_id_inner(secret: [u8; 64], id: u64) -> Result<Keypair, Box<dyn std::error::Error>> {
-        let id_bytes = id.to_be_bytes();
-        let account = u32::from_be_bytes(id_bytes[0..4].try_into()?);
-        let change = u32::from_be_bytes(id_bytes[4..8].try_into()?);
-        let derivation_path = DerivationPath::new_bip44(Some(account), Some(change));
-        Keypair::from_seed_and_derivation_path(&secret, Some(derivation_path))
+    fn key_from_id_inner(
+        secret: &[u8; 64],
+        scheme: DerivationScheme,
+        id: u64,
+    ) -> Result<Keypair, Box<dyn std::error::Error>> {
+        Keypair::from_seed_and_derivation_path(secret, Some(scheme.derivation_path(id)))
     }
 }
 
@@ -68,8 +360,12 @@ mod tests {
     use solana_sdk::pubkey;
     use solana_sdk::pubkey::Pubkey;
     use solana_sdk::signer::Signer as _;
+    use x_link_types::twitter::TwitterId;
 
     use super::*;
+    use crate::scheme::MAX_WALLET_INDEX;
+
+    static_assertions::assert_not_impl_any!(KeyGen: std::ops::Deref, Clone, Copy);
 
     #[test]
     fn test_x_key_from_id() {
@@ -90,4 +386,335 @@ mod tests {
             .expect("Error generating key");
         assert_ne!(other_keypair.pubkey(), EXPECTED);
     }
+
+    #[test]
+    fn test_scheme_vectors() {
+        const SECRET: &[u8; 64] =
+            b"What the fuck did you just fucking say about me you little bitch";
+        // Golden vectors, these must never change
+        const VECTORS: &[(u64, DerivationScheme, Pubkey)] = &[
+            (
+                0x0000_0000_0000_0000,
+                DerivationScheme::V1,
+                pubkey!("Hx5gyCPRR9SJuRFW5p1f5Nesh1AWyNahCkqoa1MDBrKk"),
+            ),
+            (
+                0x0000_0000_0000_0001,
+                DerivationScheme::V1,
+                pubkey!("8wF8Pv6yZVPnN8WD7s51MUTL9YoXhXRjTtuosPg3JX6s"),
+            ),
+            (
+                0x17e9_4c77_0b16_5000,
+                DerivationScheme::V1,
+                pubkey!("ENuzcbEgZq9j9BQCSgsFfvnMeX8aY7xGDizGyf29eByN"),
+            ),
+            (
+                0x7fff_ffff_7fff_ffff,
+                DerivationScheme::V1,
+                pubkey!("9f2XFDyznKEWN42y7U1DaPnaUWcyPraZyqq9xtHdGGFZ"),
+            ),
+            (
+                0x0000_0000_8000_0000,
+                DerivationScheme::V2,
+                pubkey!("5ZVaRD34vMa7Ho3LVW27sW13EPBLhacZwK3VypSo5trW"),
+            ),
+            (
+                0x17e9_4c77_8b16_5000,
+                DerivationScheme::V2,
+                pubkey!("GpHv27dLb3hJLLEoPdgdYTGHS89hNRBTjepW7Vvv8adx"),
+            ),
+            (
+                0x8000_0000_0000_0000,
+                DerivationScheme::V2,
+                pubkey!("3CKsQtKPPWcuwZEqpuiVPo3pkKzH3Yyc1GANvG4iZr8i"),
+            ),
+            (
+                0xffff_ffff_ffff_ffff,
+                DerivationScheme::V2,
+                pubkey!("AjfdAHQixhj8rrWMY9u6LVjPmYraMuuRSaGWmCbMtjnp"),
+            ),
+        ];
+
+        let keygen = KeyGen::from(*SECRET);
+        for (id, scheme, expected) in VECTORS {
+            let keypair = keygen
+                .derive_with_scheme(*scheme, *id)
+                .expect("Error generating key");
+            assert_eq!(
+                keypair.pubkey(),
+                *expected,
+                "{} pubkey for {:#x}",
+                scheme,
+                id
+            );
+        }
+    }
+
+    #[test]
+    fn test_served_schemes() {
+        const SECRET: &[u8; 64] =
+            b"What the fuck did you just fucking say about me you little bitch";
+        // Funded before the migration, bit 31 set
+        const FUNDED: u64 = 0x17e9_4c77_8b16_5000;
+        const FUNDED_WALLET: Pubkey = pubkey!("ENuzcbEgZq9j9BQCSgsFfvnMeX8aY7xGDizGyf29eByN");
+        // Shares the funded ID's V1 wallet
+        const PARTNER: u64 = 0x17e9_4c77_0b16_5000;
+
+        // Nothing migrated, every ID keeps its V1 wallet
+        let keygen = KeyGen::from(*SECRET);
+        let (scheme, keypair) = keygen.derive(FUNDED).unwrap();
+        assert_eq!(scheme, DerivationScheme::V1);
+        assert_eq!(keypair.pubkey(), FUNDED_WALLET);
+
+        let mut schemes = SchemeTable::default();
+        schemes.pin_ids([FUNDED]);
+        let keygen = keygen.with_schemes(schemes);
+
+        // The funded ID keeps its V1 wallet, its partner and new IDs move
+        let (scheme, keypair) = keygen.derive(FUNDED).unwrap();
+        assert_eq!(scheme, DerivationScheme::V1);
+        assert_eq!(keypair.pubkey(), FUNDED_WALLET);
+        assert_eq!(
+            keygen.derive_indexed(FUNDED, 0).unwrap().1.pubkey(),
+            FUNDED_WALLET
+        );
+
+        let (scheme, keypair) = keygen.derive(PARTNER).unwrap();
+        assert_eq!(scheme, DerivationScheme::V2);
+        assert_ne!(keypair.pubkey(), FUNDED_WALLET);
+        assert_eq!(keygen.derive(0).unwrap().0, DerivationScheme::V2);
+    }
+
+    #[test]
+    fn test_handle_vectors() {
+        const SECRET: &[u8; 64] =
+            b"What the fuck did you just fucking say about me you little bitch";
+        // Golden vectors, these must never change
+        const VECTORS: &[(&str, DerivationScheme, Pubkey)] = &[
+            (
+                "elon",
+                DerivationScheme::V1,
+                pubkey!("FPtR2hcQcNyHkz8FyLLXdSm1ySBeGwd7BiGzU5PPkvuP"),
+            ),
+            (
+                "Elon",
+                DerivationScheme::V1,
+                pubkey!("9NtkJ5n8aDcLectZkPDSPUBZ1nTmZd219acYvmFVdRo8"),
+            ),
+            (
+                "@jack",
+                DerivationScheme::V1,
+                pubkey!("B4mtVRntQoRTqcxUcUC4Lwue5SVQsWyAQWUw2RSFi4dH"),
+            ),
+            (
+                "elon",
+                DerivationScheme::V3,
+                pubkey!("GrvTAoUWj4uUBbtumJHMDDJepYX1SNm4KTqyT8nch3mf"),
+            ),
+            (
+                "Elon",
+                DerivationScheme::V3,
+                pubkey!("GrvTAoUWj4uUBbtumJHMDDJepYX1SNm4KTqyT8nch3mf"),
+            ),
+            (
+                "ＥＬＯＮ",
+                DerivationScheme::V3,
+                pubkey!("GrvTAoUWj4uUBbtumJHMDDJepYX1SNm4KTqyT8nch3mf"),
+            ),
+            (
+                "@jack",
+                DerivationScheme::V3,
+                pubkey!("BNmR8vvyYf5VKPJ1yDCEEnfxVifiBAk6Zqt96RPXg2uM"),
+            ),
+        ];
+
+        let keygen = KeyGen::from(*SECRET);
+        for (handle, scheme, expected) in VECTORS {
+            let keypair = keygen
+                .derive_handle_with_scheme(*scheme, handle)
+                .expect("Error generating key");
+            assert_eq!(
+                keypair.pubkey(),
+                *expected,
+                "{} pubkey for {}",
+                scheme,
+                handle
+            );
+        }
+
+        // Until handles are migrated every handle keeps its pre-V3 wallet
+        let keypair = keygen.generate_key("elon").expect("Error generating key");
+        assert_eq!(
+            keypair.pubkey(),
+            pubkey!("FPtR2hcQcNyHkz8FyLLXdSm1ySBeGwd7BiGzU5PPkvuP")
+        );
+
+        let mut schemes = SchemeTable::default();
+        schemes.pin_handles(["elon"]);
+        let keygen = KeyGen::from(*SECRET).with_schemes(schemes);
+        let (scheme, pinned) = keygen.derive_handle("elon").unwrap();
+        assert_eq!(scheme, DerivationScheme::V1);
+        assert_eq!(pinned.pubkey(), keypair.pubkey());
+        let (scheme, keypair) = keygen.derive_handle("@ELON").unwrap();
+        assert_eq!(scheme, DerivationScheme::V3);
+        assert_eq!(
+            keypair.pubkey(),
+            pubkey!("GrvTAoUWj4uUBbtumJHMDDJepYX1SNm4KTqyT8nch3mf")
+        );
+    }
+
+    #[test]
+    fn test_social_vectors() {
+        const SECRET: &[u8; 64] =
+            b"What the fuck did you just fucking say about me you little bitch";
+        // Golden vectors, these must never change
+        const VECTORS: &[(SocialId, Pubkey)] = &[
+            (
+                SocialId::Discord(80351110224678912),
+                pubkey!("679G7HoCYeNVsZznJcyoyK2CpWmbuSL5CjzmvaNHMpgp"),
+            ),
+            (
+                SocialId::Discord(42),
+                pubkey!("GKb5E6PE13RidPgibkmo2V9sQPxbfoe6GxQA1CB12i5w"),
+            ),
+            (
+                SocialId::Telegram(42),
+                pubkey!("BbJ3gTj8MhB8LNZHzhBBUxHuqkoYKgz1koqheiUGWjnF"),
+            ),
+            (
+                SocialId::Farcaster(42),
+                pubkey!("9ntLbZxxzbrxe7HmYVj4dGGn48zkd37fRvAHeJNHNd2c"),
+            ),
+        ];
+
+        let keygen = KeyGen::from(*SECRET);
+        for (id, expected) in VECTORS {
+            let (scheme, keypair) = keygen.derive_social(*id).expect("Error generating key");
+            assert_eq!(scheme, DerivationScheme::V3, "scheme for {}", id);
+            assert_eq!(keypair.pubkey(), *expected, "pubkey for {}", id);
+        }
+
+        // Twitter IDs keep their wallets
+        const ID: u64 = 1722992406616756224;
+        assert_eq!(
+            keygen
+                .generate_key(SocialId::Twitter(TwitterId::new(ID).unwrap()))
+                .unwrap()
+                .pubkey(),
+            keygen.generate_key(ID).unwrap().pubkey()
+        );
+        assert_ne!(
+            keygen.generate_key(SocialId::Discord(ID)).unwrap().pubkey(),
+            keygen.generate_key(ID).unwrap().pubkey()
+        );
+
+        assert!(keygen
+            .derive_social_with_scheme(DerivationScheme::V2, SocialId::Discord(42))
+            .is_err());
+    }
+
+    #[test]
+    fn test_indexed_vectors() {
+        const SECRET: &[u8; 64] =
+            b"What the fuck did you just fucking say about me you little bitch";
+        const ID: u64 = 1722992406616756224;
+
+        let keygen = KeyGen::from(*SECRET);
+        let wallet = |index| {
+            keygen
+                .derive_indexed(ID, index)
+                .expect("Error generating key")
+        };
+
+        // The existing wallet is index 0
+        assert_eq!(
+            wallet(0).1.pubkey(),
+            pubkey!("ENuzcbEgZq9j9BQCSgsFfvnMeX8aY7xGDizGyf29eByN")
+        );
+        assert_eq!(wallet(0).0, DerivationScheme::V1);
+
+        // Golden vectors, these must never change
+        assert_eq!(wallet(1).0, DerivationScheme::V2);
+        assert_eq!(
+            wallet(1).1.pubkey(),
+            pubkey!("Fow8L5TNh1tcRh9FqW1iANgBJGBqKWYJADZXPRKTXRZy")
+        );
+        assert_eq!(
+            wallet(2).1.pubkey(),
+            pubkey!("9RWVjdDsES1LsURuqZuncRV7Cqowh8E4SRPuazjjYyBs")
+        );
+
+        assert!(keygen
+            .derive_indexed_with_scheme(DerivationScheme::V1, ID, 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_evm_vectors() {
+        const SECRET: &[u8; 64] =
+            b"What the fuck did you just fucking say about me you little bitch";
+
+        let keygen = KeyGen::from(*SECRET);
+        let address = |id, index| {
+            keygen
+                .derive_evm(id, index)
+                .expect("Error generating key")
+                .to_string()
+        };
+
+        // Golden vectors, cross-checked against an independent BIP32
+        // implementation. These must never change
+        assert_eq!(address(0, 0), "0x0d99Bbb0acf25f9f13e85b440eb2cc1E5FfbF7dE");
+        assert_eq!(
+            address(0x17e9_4c77_0b16_5000, 0),
+            "0xed1B0701536Db26BB2BC2059C9C2ada1aD21B235"
+        );
+        assert_eq!(
+            address(0x8000_0000_0000_0000, 0),
+            "0x61E859fcB5B51d2aF420fb4d89E9f268da813CBa"
+        );
+        assert_eq!(
+            address(u64::MAX, 0),
+            "0xCAbBacE12A2f909D1e5e3eD6c0877DdadbDD56D6"
+        );
+        assert_eq!(
+            address(0x17e9_4c77_0b16_5000, 1),
+            "0xbb8Cfed46a942e6A671a8E4D98746E7Cb170b04d"
+        );
+
+        assert!(keygen.derive_evm(0, MAX_WALLET_INDEX + 1).is_err());
+    }
+
+    #[test]
+    fn test_v1_collides_where_v2_does_not() {
+        const SECRET: &[u8; 64] =
+            b"What the fuck did you just fucking say about me you little bitch";
+        const ID: u64 = 0x0000_0001_0000_0002;
+        const HIGH_BIT: u64 = ID | 0x8000_0000;
+
+        let keygen = KeyGen::from(*SECRET);
+        let v1 = |id| {
+            keygen
+                .derive_with_scheme(DerivationScheme::V1, id)
+                .expect("Error generating key")
+                .pubkey()
+        };
+        let v2 = |id| {
+            keygen
+                .derive_with_scheme(DerivationScheme::V2, id)
+                .expect("Error generating key")
+                .pubkey()
+        };
+
+        assert_eq!(v1(ID), v1(HIGH_BIT));
+        assert_ne!(v2(ID), v2(HIGH_BIT));
+        assert_ne!(v1(ID), v2(ID));
+
+        // Once migrated, the colliding ID moves to V2 and the other keeps its V1 wallet
+        let mut schemes = SchemeTable::default();
+        schemes.pin_ids([ID, HIGH_BIT]);
+        let migrated = KeyGen::from(*SECRET).with_schemes(schemes);
+        assert_eq!(migrated.derive(ID).unwrap().1.pubkey(), v1(ID));
+        assert_eq!(migrated.derive(HIGH_BIT).unwrap().1.pubkey(), v2(HIGH_BIT));
+    }
 }
@@ -0,0 +1,381 @@
+//! Encrypted on-disk format for the master mnemonic.
+//!
+//! A keystore is a JSON document made of a header and a ciphertext:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "kdf": { "algorithm": "argon2id", "memoryKib": 65536, "iterations": 3, "parallelism": 1, "salt": "<hex>" },
+//!   "cipher": { "algorithm": "xchacha20poly1305", "nonce": "<hex>" },
+//!   "ciphertext": "<hex>"
+//! }
+//! ```
+//!
+//! The encryption key is stretched from the passphrase with Argon2id, and the
+//! serialized header is bound to the ciphertext as associated data, so the KDF
+//! parameters can't be downgraded without the decryption failing.
+//!
+//! Keystores holding a Shamir share of the mnemonic entropy carry an extra
+//! `share` object in the header, see [`ShareInfo`].
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore as _, Aead as _, AeadCore as _, KeyInit as _, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::{error::Error, private_file};
+
+pub const KEYSTORE_VERSION: u32 = 1;
+
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+/// Upper bounds on the KDF params read from a keystore, so a crafted file
+/// can't make opening it allocate or spin without limit.
+pub const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+pub const MAX_ITERATIONS: u32 = 32;
+pub const MAX_PARALLELISM: u32 = 16;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    /// Refuse params above [`MAX_MEMORY_KIB`], [`MAX_ITERATIONS`] or [`MAX_PARALLELISM`].
+    pub fn check(&self) -> Result<(), Error> {
+        if self.memory_kib > MAX_MEMORY_KIB
+            || self.iterations > MAX_ITERATIONS
+            || self.parallelism > MAX_PARALLELISM
+        {
+            return Err(Error::Keystore(format!(
+                "kdf params out of bounds: {} KiB, {} iterations, {} lanes \
+                 (at most {} KiB, {} iterations, {} lanes)",
+                self.memory_kib,
+                self.iterations,
+                self.parallelism,
+                MAX_MEMORY_KIB,
+                MAX_ITERATIONS,
+                MAX_PARALLELISM
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "algorithm", rename_all = "lowercase")]
+pub enum Kdf {
+    Argon2id {
+        #[serde(flatten)]
+        params: KdfParams,
+        salt: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "algorithm", rename_all = "lowercase")]
+pub enum Cipher {
+    XChaCha20Poly1305 { nonce: String },
+}
+
+/// Which split a share belongs to and where it sits in it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareInfo {
+    /// Random identifier shared by every share of one split
+    pub split_id: String,
+    pub index: u8,
+    pub threshold: u8,
+    pub shares: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub version: u32,
+    pub kdf: Kdf,
+    pub cipher: Cipher,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub share: Option<ShareInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Keystore {
+    #[serde(flatten)]
+    pub header: Header,
+    pub ciphertext: String,
+}
+
+impl Keystore {
+    /// Seal `secret` under `passphrase` with freshly generated salt and nonce.
+    pub fn seal(secret: &[u8], passphrase: &str, params: KdfParams) -> Result<Self, Error> {
+        Self::seal_inner(secret, passphrase, params, None)
+    }
+
+    /// Seal one Shamir share, its [`ShareInfo`] is authenticated with the header.
+    pub fn seal_share(
+        share: &[u8],
+        info: ShareInfo,
+        passphrase: &str,
+        params: KdfParams,
+    ) -> Result<Self, Error> {
+        Self::seal_inner(share, passphrase, params, Some(info))
+    }
+
+    fn seal_inner(
+        secret: &[u8],
+        passphrase: &str,
+        params: KdfParams,
+        share: Option<ShareInfo>,
+    ) -> Result<Self, Error> {
+        if passphrase.is_empty() {
+            return Err(Error::Keystore(
+                "refusing to seal a keystore with an empty passphrase".to_string(),
+            ));
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let header = Header {
+            version: KEYSTORE_VERSION,
+            kdf: Kdf::Argon2id {
+                params,
+                salt: hex::encode(salt),
+            },
+            cipher: Cipher::XChaCha20Poly1305 {
+                nonce: hex::encode(nonce),
+            },
+            share,
+        };
+
+        let key = Self::derive_key(&header, passphrase)?;
+        let aad = serde_json::to_vec(&header)?;
+        let ciphertext = XChaCha20Poly1305::new(key.as_slice().into())
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: secret,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| Error::Keystore("encryption failed".to_string()))?;
+
+        Ok(Self {
+            header,
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    /// Decrypt the sealed secret. Fails if the passphrase is wrong or the
+    /// keystore has been tampered with.
+    pub fn open(&self, passphrase: &str) -> Result<Zeroizing<Vec<u8>>, Error> {
+        if self.header.version != KEYSTORE_VERSION {
+            return Err(Error::Keystore(format!(
+                "unsupported keystore version: {}",
+                self.header.version
+            )));
+        }
+
+        let Cipher::XChaCha20Poly1305 { nonce } = &self.header.cipher;
+        let nonce = Self::decode_hex(nonce, "nonce")?;
+        if nonce.len() != 24 {
+            return Err(Error::Keystore(format!(
+                "expected 24 byte nonce, got {}",
+                nonce.len()
+            )));
+        }
+        let ciphertext = Self::decode_hex(&self.ciphertext, "ciphertext")?;
+
+        let key = Self::derive_key(&self.header, passphrase)?;
+        let aad = serde_json::to_vec(&self.header)?;
+        XChaCha20Poly1305::new(key.as_slice().into())
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| {
+                Error::Keystore("decryption failed: wrong passphrase or corrupt keystore".into())
+            })
+    }
+
+    pub fn params(&self) -> KdfParams {
+        let Kdf::Argon2id { params, .. } = &self.header.kdf;
+        *params
+    }
+
+    pub fn share(&self) -> Option<&ShareInfo> {
+        self.header.share.as_ref()
+    }
+
+    /// Parse `contents` as a keystore, `None` if it holds a plaintext
+    /// mnemonic instead. A mnemonic never starts with a brace, so JSON that
+    /// doesn't parse as a keystore is reported as a corrupt one.
+    pub fn parse(contents: &str) -> Result<Option<Self>, Error> {
+        match serde_json::from_str(contents) {
+            Ok(keystore) => Ok(Some(keystore)),
+            Err(e) if contents.trim_start().starts_with('{') => {
+                Err(Error::Keystore(format!("corrupt keystore: {}", e)))
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn read(path: &str) -> Result<Self, Error> {
+        Self::parse(&std::fs::read_to_string(path)?)?
+            .ok_or_else(|| Error::Keystore(format!("{} is not a keystore", path)))
+    }
+
+    /// Atomically write the keystore to `path`, readable by the owner only.
+    pub fn write(&self, path: &str) -> Result<(), Error> {
+        Ok(private_file::write_atomic(
+            path,
+            &serde_json::to_vec_pretty(self)?,
+        )?)
+    }
+
+    fn derive_key(header: &Header, passphrase: &str) -> Result<Zeroizing<[u8; KEY_LEN]>, Error> {
+        let Kdf::Argon2id { params, salt } = &header.kdf;
+        params.check()?;
+        let salt = Self::decode_hex(salt, "salt")?;
+        let params = argon2::Params::new(
+            params.memory_kib,
+            params.iterations,
+            params.parallelism,
+            Some(KEY_LEN),
+        )
+        .map_err(|e| Error::Keystore(format!("invalid kdf params: {}", e)))?;
+
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut_slice())
+            .map_err(|e| Error::Keystore(format!("key derivation failed: {}", e)))?;
+        Ok(key)
+    }
+
+    fn decode_hex(value: &str, field: &str) -> Result<Vec<u8>, Error> {
+        hex::decode(value).map_err(|e| Error::Keystore(format!("invalid {}: {}", field, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Keep tests fast, these params are far too weak for real use
+    const TEST_PARAMS: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[test]
+    fn test_seal_and_open() {
+        let keystore = Keystore::seal(b"secret words", "hunter2", TEST_PARAMS).unwrap();
+        assert_eq!(keystore.params(), TEST_PARAMS);
+        assert_eq!(
+            keystore.open("hunter2").unwrap().as_slice(),
+            b"secret words"
+        );
+
+        // Round-trip through JSON
+        let json = serde_json::to_string(&keystore).unwrap();
+        let parsed = Keystore::parse(&json).unwrap().unwrap();
+        assert_eq!(parsed, keystore);
+        assert_eq!(parsed.open("hunter2").unwrap().as_slice(), b"secret words");
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Keystore::parse("abandon abandon about\n").unwrap(), None);
+
+        // Truncated or missing fields is a corrupt keystore, not a mnemonic
+        let json = serde_json::to_string(
+            &Keystore::seal(b"secret words", "hunter2", TEST_PARAMS).unwrap(),
+        )
+        .unwrap();
+        for corrupt in [&json[..json.len() / 2], r#"{ "version": 1 }"#] {
+            let err = Keystore::parse(corrupt).unwrap_err().to_string();
+            assert!(err.contains("corrupt keystore"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_kdf_bounds() {
+        let mut keystore = Keystore::seal(b"secret words", "hunter2", TEST_PARAMS).unwrap();
+        let Kdf::Argon2id { params, .. } = &mut keystore.header.kdf;
+        params.memory_kib = u32::MAX;
+        let err = keystore.open("hunter2").unwrap_err().to_string();
+        assert!(err.contains("out of bounds"), "{}", err);
+
+        let params = KdfParams {
+            iterations: MAX_ITERATIONS + 1,
+            ..TEST_PARAMS
+        };
+        assert!(Keystore::seal(b"secret words", "hunter2", params).is_err());
+        assert!(KdfParams::default().check().is_ok());
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let keystore = Keystore::seal(b"secret words", "hunter2", TEST_PARAMS).unwrap();
+        assert!(keystore.open("hunter3").is_err());
+    }
+
+    #[test]
+    fn test_tampered_header() {
+        let mut keystore = Keystore::seal(b"secret words", "hunter2", TEST_PARAMS).unwrap();
+        let Kdf::Argon2id { params, .. } = &mut keystore.header.kdf;
+        params.iterations += 1;
+        assert!(keystore.open("hunter2").is_err());
+    }
+
+    #[test]
+    fn test_share() {
+        let info = ShareInfo {
+            split_id: "00ff".to_string(),
+            index: 2,
+            threshold: 2,
+            shares: 3,
+        };
+        let keystore =
+            Keystore::seal_share(b"share bytes", info.clone(), "hunter2", TEST_PARAMS).unwrap();
+        assert_eq!(keystore.share(), Some(&info));
+        assert_eq!(keystore.open("hunter2").unwrap().as_slice(), b"share bytes");
+
+        // Plain keystores don't serialize the field at all
+        let plain = Keystore::seal(b"secret words", "hunter2", TEST_PARAMS).unwrap();
+        assert!(!serde_json::to_string(&plain).unwrap().contains("share"));
+
+        // The share metadata is bound to the ciphertext
+        let mut tampered = keystore.clone();
+        tampered.header.share.as_mut().unwrap().index = 3;
+        assert!(tampered.open("hunter2").is_err());
+    }
+
+    #[test]
+    fn test_empty_passphrase() {
+        assert!(Keystore::seal(b"secret words", "", TEST_PARAMS).is_err());
+    }
+}
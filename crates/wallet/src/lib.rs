@@ -1 +1,21 @@
+pub mod args;
+pub mod attestation;
+pub mod bulk;
+pub mod canary;
+pub mod error;
+pub mod evm;
+pub mod exports;
+pub mod handle;
+mod jsonl;
 pub mod keygen;
+pub mod keystore;
+pub mod migration;
+pub mod mnemonic;
+pub mod passphrase;
+mod private_file;
+pub mod rotation;
+pub mod scheme;
+pub mod scheme_table;
+pub mod shamir;
+pub mod shares;
+pub mod social;
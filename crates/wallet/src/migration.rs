@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use solana_sdk::{pubkey::Pubkey, signer::Signer as _};
+
+use crate::{handle, keygen::KeyGen, scheme::DerivationScheme, scheme_table::SchemeTable};
+
+/// Address under each registered scheme, keyed by version
+pub type Addresses = BTreeMap<u8, String>;
+
+/// Where a twitter ID's wallet lives under every registered scheme, and
+/// which one the migration pins it to.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationEntry {
+    pub twitter_id: u64,
+    /// Version of the scheme the ID is served under today
+    pub current: u8,
+    /// Version of the scheme the ID is served under once the migration is written
+    pub scheme: u8,
+    /// The earlier ID whose v1 wallet this one shares
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shares_v1_with: Option<u64>,
+    pub addresses: Addresses,
+    /// Versions whose address differs from the v1 address
+    pub changed_under: Vec<u8>,
+}
+
+/// Where a handle's wallet lives under every registered scheme, and which
+/// one the migration pins it to.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HandleMigrationEntry {
+    pub handle: String,
+    /// The handle as V3 and later see it
+    pub normalized: String,
+    /// Version of the scheme the handle is served under today
+    pub current: u8,
+    /// Version of the scheme the handle is served under once the migration is written
+    pub scheme: u8,
+    pub addresses: Addresses,
+    /// Versions whose address differs from the v1 address
+    pub changed_under: Vec<u8>,
+}
+
+impl MigrationEntry {
+    /// Whether the migration serves the ID a different wallet than today.
+    pub fn moved(&self) -> bool {
+        self.addresses[&self.current] != self.addresses[&self.scheme]
+    }
+}
+
+impl HandleMigrationEntry {
+    /// Whether the migration serves the handle a different wallet than today.
+    pub fn moved(&self) -> bool {
+        self.addresses[&self.current] != self.addresses[&self.scheme]
+    }
+}
+
+impl KeyGen {
+    /// Plan the migration of every served ID, see [`SchemeTable::pin_ids`]:
+    /// the table with `ids` pinned, and where each ID's wallet lives now and
+    /// after. Nothing is served differently until the table is written.
+    pub fn migration_report(
+        &self,
+        ids: impl IntoIterator<Item = u64>,
+    ) -> Result<(SchemeTable, Vec<MigrationEntry>), Box<dyn std::error::Error>> {
+        let mut schemes = self.schemes().clone();
+        let report = schemes
+            .pin_ids(ids)
+            .into_iter()
+            .map(|pinned| {
+                let (addresses, changed_under) = Self::compare_schemes(|scheme| {
+                    Ok(self.derive_with_scheme(scheme, pinned.twitter_id)?.pubkey())
+                })?;
+                Ok(MigrationEntry {
+                    twitter_id: pinned.twitter_id,
+                    current: self.schemes().for_id(pinned.twitter_id).version(),
+                    scheme: pinned.scheme.version(),
+                    shares_v1_with: pinned.shares_v1_with,
+                    addresses,
+                    changed_under,
+                })
+            })
+            .collect::<Result<_, Box<dyn std::error::Error>>>()?;
+        Ok((schemes, report))
+    }
+
+    /// Plan the migration of every served handle, see
+    /// [`SchemeTable::pin_handles`]: the table with `handles` pinned, and
+    /// where each handle's wallet lives now and after.
+    pub fn handle_migration_report<'a>(
+        &self,
+        handles: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(SchemeTable, Vec<HandleMigrationEntry>), Box<dyn std::error::Error>> {
+        let mut schemes = self.schemes().clone();
+        let report = schemes
+            .pin_handles(handles)
+            .into_iter()
+            .map(|(handle, scheme)| {
+                let (addresses, changed_under) = Self::compare_schemes(|scheme| {
+                    Ok(self.derive_handle_with_scheme(scheme, &handle)?.pubkey())
+                })?;
+                Ok(HandleMigrationEntry {
+                    normalized: handle::normalize(&handle)?,
+                    current: self.schemes().for_handle(&handle).version(),
+                    scheme: scheme.version(),
+                    handle,
+                    addresses,
+                    changed_under,
+                })
+            })
+            .collect::<Result<_, Box<dyn std::error::Error>>>()?;
+        Ok((schemes, report))
+    }
+
+    /// Derive under every scheme and note which versions differ from v1.
+    fn compare_schemes(
+        derive: impl Fn(DerivationScheme) -> Result<Pubkey, Box<dyn std::error::Error>>,
+    ) -> Result<(Addresses, Vec<u8>), Box<dyn std::error::Error>> {
+        let mut addresses = Addresses::new();
+        for scheme in DerivationScheme::ALL {
+            addresses.insert(scheme.version(), derive(scheme)?.to_string());
+        }
+
+        let legacy = &addresses[&DerivationScheme::V1.version()];
+        let changed_under = addresses
+            .iter()
+            .filter(|(_, address)| *address != legacy)
+            .map(|(version, _)| *version)
+            .collect();
+        Ok((addresses, changed_under))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8; 64] = b"What the fuck did you just fucking say about me you little bitch";
+
+    #[test]
+    fn test_migration_report() {
+        const ID: u64 = 1722992406616756224;
+        let keygen = KeyGen::from(*SECRET);
+        let (schemes, report) = keygen.migration_report([ID | 0x8000_0000, ID, 42]).unwrap();
+
+        // Served first, keeps the shared wallet
+        assert_eq!(report[0].current, 1);
+        assert_eq!(report[0].scheme, 1);
+        assert_eq!(report[0].shares_v1_with, None);
+        assert_eq!(report[0].changed_under, vec![2, 3]);
+        assert!(!report[0].moved());
+
+        assert_eq!(report[1].current, 1);
+        assert_eq!(report[1].scheme, 2);
+        assert_eq!(report[1].shares_v1_with, Some(ID | 0x8000_0000));
+        assert!(report[1].moved());
+        assert_eq!(report[0].addresses[&1], report[1].addresses[&1]);
+
+        assert!(!report[2].moved());
+
+        // Only the returned table serves the plan
+        assert!(!keygen.schemes().ids_migrated());
+        let keygen = keygen.with_schemes(schemes);
+        assert_eq!(keygen.derive(ID).unwrap().0, DerivationScheme::V2);
+        assert_eq!(
+            keygen
+                .derive(ID | 0x8000_0000)
+                .unwrap()
+                .1
+                .pubkey()
+                .to_string(),
+            report[0].addresses[&1]
+        );
+        assert_eq!(
+            report[0].addresses[&2],
+            "GpHv27dLb3hJLLEoPdgdYTGHS89hNRBTjepW7Vvv8adx"
+        );
+
+        // Migrating again changes nothing for served IDs
+        let (_, report) = keygen.migration_report([ID, 7]).unwrap();
+        assert_eq!(report[0].scheme, 2);
+        assert!(!report[0].moved());
+        assert_eq!((report[1].current, report[1].scheme), (2, 2));
+    }
+
+    #[test]
+    fn test_handle_migration_report() {
+        let keygen = KeyGen::from(*SECRET);
+        let (schemes, report) = keygen.handle_migration_report(["elon", "@Elon"]).unwrap();
+
+        // Served spellings keep their wallets
+        assert_eq!(report[1].normalized, "elon");
+        assert_eq!((report[1].current, report[1].scheme), (1, 1));
+        assert_eq!(report[1].changed_under, vec![3]);
+        assert!(!report[1].moved());
+
+        // Different wallets before V3, the same one after
+        assert_ne!(report[0].addresses[&1], report[1].addresses[&1]);
+        assert_eq!(report[0].addresses[&3], report[1].addresses[&3]);
+
+        // New spellings get V3 once the table is written
+        let keygen = keygen.with_schemes(schemes);
+        let (_, report) = keygen.handle_migration_report(["ELON"]).unwrap();
+        assert_eq!((report[0].current, report[0].scheme), (3, 3));
+        assert_eq!(
+            keygen.derive_handle("ELON").unwrap().1.pubkey().to_string(),
+            report[0].addresses[&3]
+        );
+    }
+}
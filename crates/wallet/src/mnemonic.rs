@@ -0,0 +1,39 @@
+use chacha20poly1305::aead::{rand_core::RngCore as _, OsRng};
+use zeroize::Zeroizing;
+
+use crate::error::Error;
+
+/// Word counts BIP39 defines, 24 words carry 256 bits of entropy.
+pub const WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// Generate a new mnemonic from OS entropy.
+pub fn generate(words: usize) -> Result<bip39::Mnemonic, Error> {
+    if !WORD_COUNTS.contains(&words) {
+        return Err(bip39::Error::BadWordCount(words).into());
+    }
+    // 11 bits per word, one in every 33 is checksum
+    let mut entropy = Zeroizing::new([0u8; 32]);
+    let len = words * 4 / 3;
+    OsRng.fill_bytes(&mut entropy[..len]);
+    Ok(bip39::Mnemonic::from_entropy(&entropy[..len])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        for words in WORD_COUNTS {
+            let mnemonic = generate(words).unwrap();
+            assert_eq!(mnemonic.word_count(), words);
+            assert_eq!(
+                bip39::Mnemonic::parse(mnemonic.to_string()).unwrap(),
+                mnemonic
+            );
+        }
+        assert_ne!(generate(24).unwrap(), generate(24).unwrap());
+        assert!(generate(13).is_err());
+        assert!(generate(0).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! Where the seed passphrase comes from. Services can't answer a prompt, so
+//! the passphrase can also be read from an environment variable, an inherited
+//! file descriptor or a credentials file only the current user can read.
+
+use std::fs::File;
+use std::io::Read as _;
+use std::mem::ManuallyDrop;
+use std::os::fd::{FromRawFd as _, RawFd};
+use std::os::unix::fs::{MetadataExt as _, PermissionsExt as _};
+
+use zeroize::Zeroizing;
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum PassphraseSource {
+    /// Prompt on the terminal
+    #[default]
+    Prompt,
+    /// Name of an environment variable holding the passphrase. It is left
+    /// in the environment, which can't be changed safely once other threads
+    /// run, so prefer [`Self::Fd`] or [`Self::File`] or unset it in whatever
+    /// starts the process after the passphrase is read.
+    Env(String),
+    /// File descriptor inherited from the parent, e.g. a pipe
+    Fd(RawFd),
+    /// File owned by the current user and inaccessible to anyone else
+    File(String),
+}
+
+impl PassphraseSource {
+    pub fn is_interactive(&self) -> bool {
+        matches!(self, Self::Prompt)
+    }
+
+    /// Read the passphrase, prompting with `prompt` if interactive.
+    pub fn read(&self, prompt: &str) -> Result<Zeroizing<String>, Error> {
+        match self {
+            Self::Prompt => rpassword::prompt_password(prompt)
+                .map(Zeroizing::new)
+                .map_err(|e| {
+                    Error::Passphrase(format!(
+                        "cannot prompt for a passphrase ({}), use --passphrase-env, \
+                         --passphrase-fd or --passphrase-file",
+                        e
+                    ))
+                }),
+            Self::Env(name) => std::env::var(name)
+                .map(Zeroizing::new)
+                .map_err(|e| Error::Passphrase(format!("environment variable {}: {}", name, e))),
+            Self::Fd(fd) => {
+                // The descriptor is left open, it belongs to whoever passed it
+                let file = ManuallyDrop::new(unsafe { File::from_raw_fd(*fd) });
+                let contents = read_to_end(&file)
+                    .map_err(|e| Error::Passphrase(format!("file descriptor {}: {}", fd, e)))?;
+                if contents.is_empty() {
+                    return Err(Error::Passphrase(format!(
+                        "nothing to read on file descriptor {}",
+                        fd
+                    )));
+                }
+                Ok(strip_newline(contents))
+            }
+            Self::File(path) => {
+                let file =
+                    File::open(path).map_err(|e| Error::Passphrase(format!("{}: {}", path, e)))?;
+                check_permissions(&file, path)?;
+                let contents = read_to_end(&file)
+                    .map_err(|e| Error::Passphrase(format!("{}: {}", path, e)))?;
+                Ok(strip_newline(contents))
+            }
+        }
+    }
+
+    /// Read a passphrase that is about to seal something, asking twice if
+    /// interactive so a typo doesn't lock the secret away.
+    pub fn read_new(&self) -> Result<Zeroizing<String>, Error> {
+        let passphrase = self.read("Enter passphrase: ")?;
+        if self.is_interactive() && *passphrase != *self.read("Confirm passphrase: ")? {
+            return Err(Error::Passphrase("passphrases do not match".to_string()));
+        }
+        Ok(passphrase)
+    }
+}
+
+/// Refuse credentials files that anyone but the current user could read or
+/// replace. Checked on the open file so it can't be swapped in between.
+fn check_permissions(file: &File, path: &str) -> Result<(), Error> {
+    let metadata = file.metadata()?;
+    if !metadata.is_file() {
+        return Err(Error::Passphrase(format!("{} is not a regular file", path)));
+    }
+    let uid = unsafe { libc::geteuid() };
+    if metadata.uid() != uid {
+        return Err(Error::Passphrase(format!(
+            "refusing {}: owned by uid {}, not the current user (uid {})",
+            path,
+            metadata.uid(),
+            uid
+        )));
+    }
+    let mode = metadata.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        return Err(Error::Passphrase(format!(
+            "refusing {}: mode {:03o} gives other users access, chmod 600 it",
+            path, mode
+        )));
+    }
+    Ok(())
+}
+
+fn read_to_end(mut file: &File) -> std::io::Result<Zeroizing<Vec<u8>>> {
+    let mut contents = Zeroizing::new(Vec::new());
+    file.read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+/// Files and pipes usually end with a newline that isn't part of the passphrase.
+fn strip_newline(mut contents: Zeroizing<Vec<u8>>) -> Zeroizing<String> {
+    if contents.last() == Some(&b'\n') {
+        contents.pop();
+        if contents.last() == Some(&b'\r') {
+            contents.pop();
+        }
+    }
+    Zeroizing::new(String::from_utf8_lossy(&contents).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+    use std::os::fd::AsRawFd as _;
+
+    use super::*;
+
+    fn write_file(name: &str, contents: &str, mode: u32) -> String {
+        let path = std::env::temp_dir()
+            .join(format!("x-link-{}-{}", name, std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        std::fs::write(&path, contents).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_env() {
+        // Setting a variable would race with the other tests reading theirs
+        let (name, value) = std::env::vars()
+            .find(|(_, value)| !value.is_empty())
+            .expect("an environment variable");
+        let source = PassphraseSource::Env(name.clone());
+        assert_eq!(*source.read("").unwrap(), value);
+        assert_eq!(std::env::var(&name).unwrap(), value);
+
+        let err = PassphraseSource::Env("X_LINK_TEST_UNSET_PASSPHRASE".to_string())
+            .read("")
+            .unwrap_err();
+        assert!(err.to_string().contains("X_LINK_TEST_UNSET_PASSPHRASE"));
+    }
+
+    #[test]
+    fn test_file() {
+        let path = write_file("passphrase", "hunter2\n", 0o600);
+        let source = PassphraseSource::File(path.clone());
+        assert_eq!(*source.read("").unwrap(), "hunter2");
+        assert_eq!(*source.read_new().unwrap(), "hunter2");
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+        let err = source.read("").unwrap_err().to_string();
+        assert!(err.contains("mode 640"), "{}", err);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(
+            PassphraseSource::File(std::env::temp_dir().to_str().unwrap().to_string())
+                .read("")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fd() {
+        let path = write_file("passphrase-fd", "hunter2\r\n", 0o600);
+        let file = File::open(&path).unwrap();
+        let source = PassphraseSource::Fd(file.as_raw_fd());
+        assert_eq!(*source.read("").unwrap(), "hunter2");
+        // Already consumed
+        assert!(source.read("").is_err());
+        drop(file);
+        std::fs::remove_file(&path).unwrap();
+
+        // A deliberately empty passphrase is just a newline
+        let mut file = File::create(&path).unwrap();
+        file.write_all(b"\n").unwrap();
+        let file = File::open(&path).unwrap();
+        assert_eq!(
+            *PassphraseSource::Fd(file.as_raw_fd()).read("").unwrap(),
+            ""
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,55 @@
+//! Files only the current user may read, such as keystores and canaries.
+
+use std::io::Write as _;
+
+use chacha20poly1305::aead::{rand_core::RngCore as _, OsRng};
+
+/// Atomically replace `path` with `contents`, readable by the owner only.
+///
+/// The contents go to a freshly named temporary file next to `path` first,
+/// created exclusively so nothing else can have opened or linked it before.
+pub(crate) fn write_atomic(path: &str, contents: &[u8]) -> std::io::Result<()> {
+    let mut suffix = [0u8; 8];
+    OsRng.fill_bytes(&mut suffix);
+    let tmp = format!("{}.{}.tmp", path, hex::encode(suffix));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(&tmp)?;
+    let written = file
+        .write_all(contents)
+        .and_then(|_| file.sync_all())
+        .and_then(|_| std::fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    written
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = std::env::temp_dir().join(format!("x-link-private-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("secret.json");
+        let path = path.to_str().unwrap();
+
+        write_atomic(path, b"first").unwrap();
+        write_atomic(path, b"second").unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), b"second");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            let mode = std::fs::metadata(path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        // No temporary files left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,151 @@
+//! Progress of moving every wallet to a new master seed with
+//! `x-link-wallet rotate`, as JSON lines. Each wallet of a twitter ID gets a
+//! record once its sweep is done or has failed, reruns skip the ones that
+//! are done.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Error, jsonl};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RotationStatus {
+    /// Everything was moved to the new wallet
+    Swept,
+    /// The old wallet held nothing
+    Empty,
+    /// Retried on the next run
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RotationRecord {
+    pub twitter_id: u64,
+    /// Which of the ID's wallets was swept, logs from before sub-wallets
+    /// were swept only hold main wallets
+    #[serde(default)]
+    pub wallet_index: u32,
+    /// Wallet under the old seed
+    pub from: String,
+    /// Wallet under the new seed
+    pub to: String,
+    pub status: RotationStatus,
+    /// Transactions sent for the sweep
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Unix time the sweep finished
+    pub at: u64,
+}
+
+/// The latest record of every wallet in a progress log, by twitter ID and
+/// wallet index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Progress(BTreeMap<(u64, u32), RotationRecord>);
+
+impl Progress {
+    /// Load the log at `path`, a missing log has no progress.
+    pub fn load(path: &str) -> Result<Self, Error> {
+        Ok(Self(
+            jsonl::read::<RotationRecord>(path)?
+                .into_iter()
+                .map(|record| ((record.twitter_id, record.wallet_index), record))
+                .collect(),
+        ))
+    }
+
+    /// Append `record` to the log at `path` and to this progress.
+    pub fn record(&mut self, path: &str, record: RotationRecord) -> Result<(), Error> {
+        jsonl::append(path, &record)?;
+        self.0
+            .insert((record.twitter_id, record.wallet_index), record);
+        Ok(())
+    }
+
+    pub fn get(&self, twitter_id: u64, wallet_index: u32) -> Option<&RotationRecord> {
+        self.0.get(&(twitter_id, wallet_index))
+    }
+
+    /// Whether the ID's wallet needs no more sweeping.
+    pub fn is_done(&self, twitter_id: u64, wallet_index: u32) -> bool {
+        self.get(twitter_id, wallet_index)
+            .is_some_and(|record| record.status != RotationStatus::Failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(twitter_id: u64, status: RotationStatus) -> RotationRecord {
+        RotationRecord {
+            twitter_id,
+            wallet_index: 0,
+            from: format!("old-{}", twitter_id),
+            to: format!("new-{}", twitter_id),
+            status,
+            signatures: Vec::new(),
+            error: (status == RotationStatus::Failed).then(|| "error".to_string()),
+            at: 10,
+        }
+    }
+
+    #[test]
+    fn test_progress() {
+        let path = std::env::temp_dir()
+            .join(format!("x-link-rotation-{}", std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        let mut progress = Progress::load(&path).unwrap();
+        assert_eq!(progress, Progress::default());
+
+        progress
+            .record(&path, record(1, RotationStatus::Failed))
+            .unwrap();
+        progress
+            .record(&path, record(2, RotationStatus::Empty))
+            .unwrap();
+        progress
+            .record(&path, record(3, RotationStatus::Failed))
+            .unwrap();
+        progress
+            .record(&path, record(1, RotationStatus::Swept))
+            .unwrap();
+        let sub_wallet = RotationRecord {
+            wallet_index: 2,
+            ..record(3, RotationStatus::Swept)
+        };
+        progress.record(&path, sub_wallet.clone()).unwrap();
+
+        let progress = Progress::load(&path).unwrap();
+        assert!(progress.is_done(1, 0));
+        assert!(progress.is_done(2, 0));
+        assert!(!progress.is_done(3, 0));
+        assert!(!progress.is_done(4, 0));
+        assert_eq!(progress.get(3, 0), Some(&record(3, RotationStatus::Failed)));
+        // Sub-wallets are tracked on their own
+        assert!(progress.is_done(3, 2));
+        assert!(!progress.is_done(1, 2));
+        assert_eq!(progress.get(3, 2), Some(&sub_wallet));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_record_without_index() {
+        let record: RotationRecord = serde_json::from_value(serde_json::json!({
+            "twitterId": 1,
+            "from": "old-1",
+            "to": "new-1",
+            "status": "swept",
+            "at": 10,
+        }))
+        .unwrap();
+        assert_eq!(record.wallet_index, 0);
+    }
+}
@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::derivation_path::DerivationPath;
+
+use crate::error::Error;
+
+/// Registry of the ways twitter IDs, handles and other platforms' IDs are
+/// mapped to wallets under the master seed.
+///
+/// Every version stays derivable forever, which one serves an ID is recorded
+/// in its [`SchemeTable`](crate::scheme_table::SchemeTable). New schemes are
+/// appended with the next version number. Serialized as the version number.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(try_from = "u8", into = "u8")]
+pub enum DerivationScheme {
+    /// `m/44'/501'/{id >> 32}'/{id & 0xffffffff}'`
+    ///
+    /// Hardened indices only have 31 bits, so the top bit of each half is
+    /// dropped and IDs that differ only in those bits share a wallet.
+    V1,
+    /// `m/44'/501'/{id >> 62}'/{(id >> 31) & 0x7fffffff}'/{id & 0x7fffffff}'`
+    ///
+    /// Splits the ID into 2, 31 and 31 bit indices, so every u64 gets its own path.
+    ///
+    /// Sub-wallets append their index: `m/44'/501'/{id >> 62}'/{(id >> 31) & 0x7fffffff}'/{id & 0x7fffffff}'/{index}'`.
+    /// No ID's own wallet is that deep, so sub-wallets never collide with one.
+    V2,
+    /// Same ID paths as V2.
+    ///
+    /// Handles are normalized and derived with HKDF-SHA512 under explicit
+    /// context labels, see [`crate::handle`]. V1 and V2 hash the raw handle
+    /// appended to the seed with SHA-512.
+    ///
+    /// IDs on other platforms are derived the same way under per-platform
+    /// labels, see [`crate::social`]. Earlier schemes can't derive them.
+    V3,
+}
+
+const INDEX_MASK: u64 = 0x7fff_ffff;
+
+/// Highest sub-wallet index, indices are hardened path components.
+pub const MAX_WALLET_INDEX: u32 = INDEX_MASK as u32;
+
+impl DerivationScheme {
+    /// Every registered scheme, oldest first.
+    pub const ALL: [Self; 3] = [Self::V1, Self::V2, Self::V3];
+
+    pub const LATEST: Self = Self::V3;
+
+    pub fn version(&self) -> u8 {
+        match self {
+            Self::V1 => 1,
+            Self::V2 => 2,
+            Self::V3 => 3,
+        }
+    }
+
+    /// Whether no other ID derives the same key as `id` under this scheme.
+    pub fn is_injective_for(&self, id: u64) -> bool {
+        match self {
+            Self::V1 => (id >> 32) <= INDEX_MASK && (id & u32::MAX as u64) <= INDEX_MASK,
+            Self::V2 | Self::V3 => true,
+        }
+    }
+
+    /// `id` without the bits V1 drops, every ID with the same alias shares
+    /// one V1 wallet.
+    pub fn v1_alias(id: u64) -> u64 {
+        id & (INDEX_MASK << 32 | INDEX_MASK)
+    }
+
+    pub fn derivation_path(&self, id: u64) -> DerivationPath {
+        match self {
+            Self::V1 => DerivationPath::new_bip44(Some((id >> 32) as u32), Some(id as u32)),
+            Self::V2 | Self::V3 => DerivationPath::from_absolute_path_str(&format!(
+                "m/44'/501'/{}'/{}'/{}'",
+                id >> 62,
+                (id >> 31) & INDEX_MASK,
+                id & INDEX_MASK
+            ))
+            .expect("valid derivation path"),
+        }
+    }
+}
+
+impl DerivationScheme {
+    /// Path of sub-wallet `index` of `id`, index 0 is [`Self::derivation_path`].
+    pub fn wallet_path(&self, id: u64, index: u32) -> Result<DerivationPath, Error> {
+        match (self, index) {
+            (_, 0) => Ok(self.derivation_path(id)),
+            (Self::V2 | Self::V3, 1..=MAX_WALLET_INDEX) => {
+                Ok(DerivationPath::from_absolute_path_str(&format!(
+                    "m/44'/501'/{}'/{}'/{}'/{}'",
+                    id >> 62,
+                    (id >> 31) & INDEX_MASK,
+                    id & INDEX_MASK,
+                    index
+                ))
+                .expect("valid derivation path"))
+            }
+            _ => Err(Error::UnsupportedWalletIndex {
+                index,
+                scheme: self.to_string(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<u8> for DerivationScheme {
+    type Error = Error;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|scheme| scheme.version() == version)
+            .ok_or(Error::UnknownScheme(version))
+    }
+}
+
+impl From<DerivationScheme> for u8 {
+    fn from(scheme: DerivationScheme) -> Self {
+        scheme.version()
+    }
+}
+
+impl std::fmt::Display for DerivationScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.version())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_v1_alias() {
+        const ID: u64 = 0x0000_0001_0000_0002;
+        for id in [ID | 0x8000_0000, ID | 1 << 63, ID | 1 << 63 | 0x8000_0000] {
+            assert!(!DerivationScheme::V1.is_injective_for(id));
+            assert_eq!(DerivationScheme::v1_alias(id), ID);
+        }
+        assert!(DerivationScheme::V1.is_injective_for(ID));
+        assert_eq!(DerivationScheme::v1_alias(ID), ID);
+    }
+
+    #[test]
+    fn test_registry() {
+        for (i, scheme) in DerivationScheme::ALL.into_iter().enumerate() {
+            assert_eq!(scheme.version() as usize, i + 1);
+            assert_eq!(
+                DerivationScheme::try_from(scheme.version()).unwrap(),
+                scheme
+            );
+        }
+        assert_eq!(
+            DerivationScheme::ALL.last(),
+            Some(&DerivationScheme::LATEST)
+        );
+        assert!(DerivationScheme::try_from(0).is_err());
+
+        assert_eq!(
+            serde_json::to_value(DerivationScheme::V2).unwrap(),
+            serde_json::json!(2)
+        );
+        assert_eq!(
+            serde_json::from_value::<DerivationScheme>(serde_json::json!(3)).unwrap(),
+            DerivationScheme::V3
+        );
+        assert!(serde_json::from_value::<DerivationScheme>(serde_json::json!(9)).is_err());
+    }
+
+    #[test]
+    fn test_wallet_path() {
+        const ID: u64 = 0x0000_0001_0000_0002;
+        assert_eq!(
+            DerivationScheme::V1.wallet_path(ID, 0).unwrap(),
+            DerivationScheme::V1.derivation_path(ID)
+        );
+        assert_eq!(
+            DerivationScheme::V2.wallet_path(ID, 7).unwrap(),
+            DerivationPath::from_absolute_path_str("m/44'/501'/0'/2'/2'/7'").unwrap()
+        );
+        assert_eq!(
+            DerivationScheme::V3
+                .wallet_path(ID, MAX_WALLET_INDEX)
+                .unwrap(),
+            DerivationPath::from_absolute_path_str("m/44'/501'/0'/2'/2'/2147483647'").unwrap()
+        );
+        assert!(DerivationScheme::V1.wallet_path(ID, 1).is_err());
+        assert!(DerivationScheme::V2
+            .wallet_path(ID, MAX_WALLET_INDEX + 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_derivation_path() {
+        assert_eq!(
+            DerivationScheme::V1.derivation_path(0x0000_0001_0000_0002),
+            DerivationPath::from_absolute_path_str("m/44'/501'/1'/2'").unwrap()
+        );
+        assert_eq!(
+            DerivationScheme::V2.derivation_path(u64::MAX),
+            DerivationPath::from_absolute_path_str("m/44'/501'/3'/2147483647'/2147483647'")
+                .unwrap()
+        );
+        assert_eq!(
+            DerivationScheme::V2.derivation_path(0x8000_0000),
+            DerivationPath::from_absolute_path_str("m/44'/501'/0'/1'/0'").unwrap()
+        );
+    }
+}
@@ -0,0 +1,336 @@
+//! Which derivation scheme each twitter ID and handle is served under,
+//! stored as JSON next to the secret file.
+//!
+//! Until IDs are migrated there is no table, and every ID keeps the V1 wallet
+//! it has always been served. Migrating pins every ID that already has a
+//! wallet to the scheme of that wallet, only an ID sharing its V1 wallet with
+//! an earlier one moves to V2. From then on IDs missing from the table are
+//! new users and get V2, so the switch never moves a served wallet.
+//!
+//! Handles are migrated on their own the same way: until then every handle
+//! keeps its V1 wallet, hashed from the handle exactly as it was spelled.
+//! Migrating pins every served spelling to it, and new handles get V3.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use x_link_types::social::SocialId;
+
+use crate::{error::Error, private_file, scheme::DerivationScheme};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemeTable {
+    /// Scheme of every ID served before the migration, `None` until IDs
+    /// are migrated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ids: Option<BTreeMap<u64, DerivationScheme>>,
+    /// Scheme of every handle served before the migration, keyed by the
+    /// handle as it was spelled, `None` until handles are migrated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    handles: Option<BTreeMap<String, DerivationScheme>>,
+}
+
+/// Where [`SchemeTable::pin_ids`] put an ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PinnedId {
+    pub twitter_id: u64,
+    pub scheme: DerivationScheme,
+    /// The earlier ID whose V1 wallet this one shares, if any
+    pub shares_v1_with: Option<u64>,
+}
+
+impl SchemeTable {
+    pub fn path_for(secret_file: &str) -> String {
+        format!("{}.schemes", secret_file)
+    }
+
+    /// The table next to each of `files`, which must all agree. No table
+    /// means nothing has been migrated.
+    pub fn load<'a>(files: impl IntoIterator<Item = &'a str>) -> Result<Self, Error> {
+        let mut loaded: Option<(&str, Self)> = None;
+        for file in files {
+            let Some(table) = Self::read(file)? else {
+                continue;
+            };
+            match &loaded {
+                Some((first, first_table)) if *first_table != table => {
+                    return Err(Error::SchemeTable(format!(
+                        "the tables next to {} and {} differ",
+                        first, file
+                    )))
+                }
+                Some(_) => {}
+                None => loaded = Some((file, table)),
+            }
+        }
+        Ok(loaded.map(|(_, table)| table).unwrap_or_default())
+    }
+
+    /// Read the table stored next to `secret_file`, if there is one.
+    pub fn read(secret_file: &str) -> Result<Option<Self>, Error> {
+        match std::fs::read_to_string(Self::path_for(secret_file)) {
+            Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Atomically store the table next to `secret_file`, readable by the
+    /// owner only.
+    pub fn write(&self, secret_file: &str) -> Result<(), Error> {
+        Ok(private_file::write_atomic(
+            &Self::path_for(secret_file),
+            &serde_json::to_vec_pretty(self)?,
+        )?)
+    }
+
+    /// A migrated table with nothing pinned, for a seed nobody has been
+    /// served from yet.
+    pub fn fresh() -> Self {
+        Self {
+            ids: Some(BTreeMap::new()),
+            handles: Some(BTreeMap::new()),
+        }
+    }
+
+    /// Whether IDs have been migrated, so unpinned IDs get V2.
+    pub fn ids_migrated(&self) -> bool {
+        self.ids.is_some()
+    }
+
+    /// Whether handles have been migrated, so unpinned handles get V3.
+    pub fn handles_migrated(&self) -> bool {
+        self.handles.is_some()
+    }
+
+    /// The scheme a twitter ID's main wallet is served under.
+    pub fn for_id(&self, id: u64) -> DerivationScheme {
+        match &self.ids {
+            None => DerivationScheme::V1,
+            Some(pinned) => pinned.get(&id).copied().unwrap_or(DerivationScheme::V2),
+        }
+    }
+
+    /// The scheme sub-wallet `index` of a twitter ID is served under. Index 0
+    /// is the ID's own wallet, see [`Self::for_id`]. V1 has no sub-wallets,
+    /// so every other index uses V2.
+    pub fn for_wallet(&self, id: u64, index: u32) -> DerivationScheme {
+        match index {
+            0 => self.for_id(id),
+            _ => DerivationScheme::V2,
+        }
+    }
+
+    /// The scheme an ID on any platform is served under, twitter IDs as in
+    /// [`Self::for_id`]. Only V3 derives the other platforms.
+    pub fn for_social(&self, id: &SocialId) -> DerivationScheme {
+        match id {
+            SocialId::Twitter(id) => self.for_id(id.get()),
+            _ => DerivationScheme::V3,
+        }
+    }
+
+    /// The scheme a handle's wallet is served under.
+    pub fn for_handle(&self, handle: &str) -> DerivationScheme {
+        match &self.handles {
+            None => DerivationScheme::V1,
+            Some(pinned) => pinned.get(handle).copied().unwrap_or(DerivationScheme::V3),
+        }
+    }
+
+    /// Pin every ID in `ids`, in order, to the scheme it is served under.
+    ///
+    /// On the first migration each ID keeps its V1 wallet unless an earlier
+    /// ID in `ids` or the table already holds it, then it moves to V2. List
+    /// every ID that has been served, oldest first: any left out moves to V2
+    /// once the table is written. Later migrations pin unpinned IDs to the V2
+    /// wallet they already get.
+    pub fn pin_ids(&mut self, ids: impl IntoIterator<Item = u64>) -> Vec<PinnedId> {
+        let first = self.ids.is_none();
+        let pinned = self.ids.get_or_insert_with(BTreeMap::new);
+        let mut v1_holders = pinned
+            .iter()
+            .filter(|(_, scheme)| **scheme == DerivationScheme::V1)
+            .map(|(id, _)| (DerivationScheme::v1_alias(*id), *id))
+            .collect::<HashMap<_, _>>();
+
+        ids.into_iter()
+            .map(|twitter_id| {
+                if let Some(scheme) = pinned.get(&twitter_id) {
+                    return PinnedId {
+                        twitter_id,
+                        scheme: *scheme,
+                        shares_v1_with: None,
+                    };
+                }
+                let shares_v1_with = v1_holders
+                    .get(&DerivationScheme::v1_alias(twitter_id))
+                    .copied();
+                let scheme = if first && shares_v1_with.is_none() {
+                    v1_holders.insert(DerivationScheme::v1_alias(twitter_id), twitter_id);
+                    DerivationScheme::V1
+                } else {
+                    DerivationScheme::V2
+                };
+                pinned.insert(twitter_id, scheme);
+                PinnedId {
+                    twitter_id,
+                    scheme,
+                    shares_v1_with,
+                }
+            })
+            .collect()
+    }
+
+    /// Pin every handle in `handles`, spelled exactly as it was served, to
+    /// the scheme it is served under: V1 on the first migration, no two
+    /// spellings share a V1 wallet. Any handle left out moves to V3 once the
+    /// table is written.
+    pub fn pin_handles<'a>(
+        &mut self,
+        handles: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<(String, DerivationScheme)> {
+        let first = self.handles.is_none();
+        let pinned = self.handles.get_or_insert_with(BTreeMap::new);
+        handles
+            .into_iter()
+            .map(|handle| {
+                let scheme = *pinned.entry(handle.to_string()).or_insert(if first {
+                    DerivationScheme::V1
+                } else {
+                    DerivationScheme::V3
+                });
+                (handle.to_string(), scheme)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: u64 = 0x0000_0001_0000_0002;
+    const HIGH_BIT: u64 = ID | 0x8000_0000;
+
+    #[test]
+    fn test_unmigrated() {
+        let table = SchemeTable::default();
+        assert!(!table.ids_migrated());
+        for id in [0, ID, HIGH_BIT, u64::MAX] {
+            assert_eq!(table.for_id(id), DerivationScheme::V1);
+        }
+        assert_eq!(table.for_wallet(ID, 1), DerivationScheme::V2);
+        assert_eq!(
+            table.for_social(&SocialId::Discord(ID)),
+            DerivationScheme::V3
+        );
+        assert!(!table.handles_migrated());
+        assert_eq!(table.for_handle("Elon"), DerivationScheme::V1);
+
+        let table = SchemeTable::fresh();
+        assert_eq!(table.for_id(ID), DerivationScheme::V2);
+        assert_eq!(table.for_handle("Elon"), DerivationScheme::V3);
+    }
+
+    #[test]
+    fn test_pin_ids() {
+        let mut table = SchemeTable::default();
+        let pinned = table.pin_ids([HIGH_BIT, ID, 42, 42]);
+        assert_eq!(
+            pinned,
+            vec![
+                // Served first, keeps the wallet
+                PinnedId {
+                    twitter_id: HIGH_BIT,
+                    scheme: DerivationScheme::V1,
+                    shares_v1_with: None,
+                },
+                PinnedId {
+                    twitter_id: ID,
+                    scheme: DerivationScheme::V2,
+                    shares_v1_with: Some(HIGH_BIT),
+                },
+                PinnedId {
+                    twitter_id: 42,
+                    scheme: DerivationScheme::V1,
+                    shares_v1_with: None,
+                },
+                PinnedId {
+                    twitter_id: 42,
+                    scheme: DerivationScheme::V1,
+                    shares_v1_with: None,
+                },
+            ]
+        );
+        assert_eq!(table.for_id(HIGH_BIT), DerivationScheme::V1);
+        assert_eq!(table.for_id(ID), DerivationScheme::V2);
+        // New users get V2, even where V1 would be free
+        assert_eq!(table.for_id(43), DerivationScheme::V2);
+
+        // Later migrations never move a served ID
+        let pinned = table.pin_ids([43, HIGH_BIT | 1 << 63]);
+        assert!(pinned.iter().all(|pin| pin.scheme == DerivationScheme::V2));
+        assert_eq!(table.for_id(HIGH_BIT), DerivationScheme::V1);
+        assert!(!table.handles_migrated());
+    }
+
+    #[test]
+    fn test_pin_handles() {
+        let mut table = SchemeTable::default();
+        assert_eq!(
+            table.pin_handles(["elon", "@Elon"]),
+            vec![
+                ("elon".to_string(), DerivationScheme::V1),
+                ("@Elon".to_string(), DerivationScheme::V1),
+            ]
+        );
+        assert_eq!(table.for_handle("@Elon"), DerivationScheme::V1);
+        assert_eq!(table.for_handle("ELON"), DerivationScheme::V3);
+        assert!(!table.ids_migrated());
+
+        // Later migrations never move a served handle
+        table.pin_handles(["ELON"]);
+        assert_eq!(table.for_handle("ELON"), DerivationScheme::V3);
+        assert_eq!(table.for_handle("elon"), DerivationScheme::V1);
+    }
+
+    #[test]
+    fn test_load() {
+        let secret_file = std::env::temp_dir()
+            .join(format!("x-link-schemes-{}", std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        let other_file = format!("{}-other", secret_file);
+        assert_eq!(
+            SchemeTable::load([secret_file.as_str()]).unwrap(),
+            SchemeTable::default()
+        );
+
+        let mut table = SchemeTable::default();
+        table.pin_ids([HIGH_BIT, ID]);
+        table.write(&secret_file).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(SchemeTable::path_for(&secret_file))
+                .unwrap()
+                .parse::<serde_json::Value>()
+                .unwrap(),
+            serde_json::json!({
+                "ids": { "4294967298": 2, "6442450946": 1 }
+            })
+        );
+        assert_eq!(
+            SchemeTable::load([secret_file.as_str(), other_file.as_str()]).unwrap(),
+            table
+        );
+
+        // Share files carrying different tables are refused
+        SchemeTable::fresh().write(&other_file).unwrap();
+        assert!(SchemeTable::load([secret_file.as_str(), other_file.as_str()]).is_err());
+
+        std::fs::remove_file(SchemeTable::path_for(&secret_file)).unwrap();
+        std::fs::remove_file(SchemeTable::path_for(&other_file)).unwrap();
+    }
+}
@@ -0,0 +1,176 @@
+//! Shamir secret sharing over GF(2^8), byte by byte.
+//!
+//! Field arithmetic uses the AES polynomial (x^8 + x^4 + x^3 + x + 1) and is
+//! branch-free on secret data. Polynomial coefficients are drawn uniformly
+//! from the whole field, zero included.
+
+use chacha20poly1305::aead::{rand_core::RngCore as _, OsRng};
+use zeroize::{Zeroize as _, Zeroizing};
+
+use crate::error::Error;
+
+/// A share of a secret: the x coordinate and one y coordinate per secret byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share {
+    pub index: u8,
+    pub data: Vec<u8>,
+}
+
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.data.zeroize();
+    }
+}
+
+/// Split `secret` into `count` shares, any `threshold` of which recover it.
+pub fn split(secret: &[u8], threshold: u8, count: u8) -> Result<Vec<Share>, Error> {
+    if threshold < 2 || count < threshold {
+        return Err(Error::Shamir(format!(
+            "invalid {}-of-{} split, need 2 <= threshold <= shares",
+            threshold, count
+        )));
+    }
+
+    let mut shares = (1..=count)
+        .map(|index| Share {
+            index,
+            data: Vec::with_capacity(secret.len()),
+        })
+        .collect::<Vec<_>>();
+
+    let mut coefficients = Zeroizing::new(vec![0u8; threshold as usize]);
+    for byte in secret {
+        coefficients[0] = *byte;
+        OsRng.fill_bytes(&mut coefficients[1..]);
+        for share in shares.iter_mut() {
+            share.data.push(evaluate(&coefficients, share.index));
+        }
+    }
+    Ok(shares)
+}
+
+/// Recover the secret from shares of a single split.
+///
+/// There is no way to tell from the shares alone whether enough of them were
+/// given, fewer than the threshold silently recover garbage.
+pub fn combine(shares: &[Share]) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let Some(first) = shares.first() else {
+        return Err(Error::Shamir("no shares given".to_string()));
+    };
+    for (i, share) in shares.iter().enumerate() {
+        if share.index == 0 {
+            return Err(Error::Shamir("share index can't be zero".to_string()));
+        }
+        if share.data.len() != first.data.len() {
+            return Err(Error::Shamir("shares have different lengths".to_string()));
+        }
+        if shares[..i].iter().any(|other| other.index == share.index) {
+            return Err(Error::Shamir(format!("duplicate share {}", share.index)));
+        }
+    }
+
+    // Lagrange basis polynomials evaluated at x = 0
+    let basis = shares
+        .iter()
+        .map(|share| {
+            shares
+                .iter()
+                .filter(|other| other.index != share.index)
+                .fold(1, |acc, other| {
+                    mul(acc, mul(other.index, inv(other.index ^ share.index)))
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let mut secret = Zeroizing::new(vec![0u8; first.data.len()]);
+    for (i, byte) in secret.iter_mut().enumerate() {
+        *byte = shares
+            .iter()
+            .zip(&basis)
+            .fold(0, |acc, (share, basis)| acc ^ mul(share.data[i], *basis));
+    }
+    Ok(secret)
+}
+
+/// Evaluate the polynomial at `x` with Horner's method, constant term first.
+fn evaluate(coefficients: &[u8], x: u8) -> u8 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(0, |acc, coefficient| mul(acc, x) ^ coefficient)
+}
+
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        let carry = (a >> 7).wrapping_neg();
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse as a^254, zero maps to zero.
+fn inv(a: u8) -> u8 {
+    let a2 = mul(a, a);
+    let a4 = mul(a2, a2);
+    let a8 = mul(a4, a4);
+    let a16 = mul(a8, a8);
+    let a32 = mul(a16, a16);
+    let a64 = mul(a32, a32);
+    let a128 = mul(a64, a64);
+    // 254 = 128 + 64 + 32 + 16 + 8 + 4 + 2
+    [a64, a32, a16, a8, a4, a2].into_iter().fold(a128, mul)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field() {
+        // Known AES field values
+        assert_eq!(mul(0x57, 0x83), 0xc1);
+        assert_eq!(mul(0x57, 0x13), 0xfe);
+        assert_eq!(inv(0), 0);
+        for a in 1..=255u8 {
+            assert_eq!(mul(a, inv(a)), 1, "inverse of {:#x}", a);
+        }
+    }
+
+    #[test]
+    fn test_split_and_combine() {
+        let secret = b"correct horse battery staple";
+        let shares = split(secret, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        // Every 3 share subset recovers the secret
+        for a in 0..5 {
+            for b in a + 1..5 {
+                for c in b + 1..5 {
+                    let subset = [shares[a].clone(), shares[b].clone(), shares[c].clone()];
+                    assert_eq!(combine(&subset).unwrap().as_slice(), secret);
+                }
+            }
+        }
+        assert_eq!(combine(&shares).unwrap().as_slice(), secret);
+
+        // Two shares aren't enough
+        assert_ne!(combine(&shares[..2]).unwrap().as_slice(), secret);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(split(b"secret", 1, 3).is_err());
+        assert!(split(b"secret", 4, 3).is_err());
+
+        let shares = split(b"secret", 2, 3).unwrap();
+        assert!(combine(&[]).is_err());
+        assert!(combine(&[shares[0].clone(), shares[0].clone()]).is_err());
+
+        let mut short = shares[1].clone();
+        short.data.pop();
+        assert!(combine(&[shares[0].clone(), short]).is_err());
+    }
+}
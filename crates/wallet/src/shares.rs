@@ -0,0 +1,170 @@
+//! k-of-n splitting of the master mnemonic.
+//!
+//! The mnemonic entropy is split with [`crate::shamir`] and every share is
+//! sealed in its own [`Keystore`] under its holder's passphrase. Any
+//! `threshold` of the shares, plus the BIP39 passphrase, recover the seed.
+
+use chacha20poly1305::aead::{rand_core::RngCore as _, OsRng};
+use zeroize::Zeroizing;
+
+use crate::{
+    error::Error,
+    keygen::KeyGen,
+    keystore::{KdfParams, Keystore, ShareInfo},
+    shamir::{self, Share},
+};
+
+const SPLIT_ID_LEN: usize = 8;
+
+/// Split the mnemonic behind `secret_file` into one sealed share per entry
+/// of `share_passphrases`, any `threshold` of which recover it.
+///
+/// `passphrase` unlocks `secret_file` and is checked against its canary, if
+/// there is one. It is not needed by the share holders.
+pub fn split(
+    secret_file: &str,
+    passphrase: &str,
+    threshold: u8,
+    share_passphrases: &[Zeroizing<String>],
+    params: KdfParams,
+) -> Result<Vec<Keystore>, Box<dyn std::error::Error>> {
+    let count = u8::try_from(share_passphrases.len())
+        .map_err(|_| Error::Shamir("at most 255 shares are supported".to_string()))?;
+
+    let mnemonic = KeyGen::read_mnemonic(secret_file, passphrase)?;
+    // Catch a mistyped passphrase before it's handed out in shares
+    KeyGen::from(mnemonic.to_seed(passphrase)).verify_canaries([secret_file])?;
+    Ok(split_mnemonic(
+        &mnemonic,
+        threshold,
+        count,
+        share_passphrases,
+        params,
+    )?)
+}
+
+/// Open every share with its holder's passphrase and recover the mnemonic.
+///
+/// All shares must come from the same split, and there must be at least as
+/// many as its threshold.
+pub fn combine(shares: &[(Keystore, Zeroizing<String>)]) -> Result<bip39::Mnemonic, Error> {
+    let Some(first) = shares.first() else {
+        return Err(Error::Shamir("no shares given".to_string()));
+    };
+    let info = share_info(&first.0)?;
+
+    let mut opened = Vec::with_capacity(shares.len());
+    for (keystore, passphrase) in shares {
+        let share = share_info(keystore)?;
+        if share.split_id != info.split_id || share.threshold != info.threshold {
+            return Err(Error::Shamir(format!(
+                "share {} belongs to a different split",
+                share.index
+            )));
+        }
+        opened.push(Share {
+            index: share.index,
+            data: keystore.open(passphrase)?.to_vec(),
+        });
+    }
+    if opened.len() < info.threshold as usize {
+        return Err(Error::Shamir(format!(
+            "need {} shares, got {}",
+            info.threshold,
+            opened.len()
+        )));
+    }
+
+    let entropy = shamir::combine(&opened)?;
+    Ok(bip39::Mnemonic::from_entropy(&entropy)?)
+}
+
+fn split_mnemonic(
+    mnemonic: &bip39::Mnemonic,
+    threshold: u8,
+    count: u8,
+    share_passphrases: &[Zeroizing<String>],
+    params: KdfParams,
+) -> Result<Vec<Keystore>, Error> {
+    let mut split_id = [0u8; SPLIT_ID_LEN];
+    OsRng.fill_bytes(&mut split_id);
+
+    let entropy = Zeroizing::new(mnemonic.to_entropy());
+    let shares = shamir::split(&entropy, threshold, count)?;
+    shares
+        .iter()
+        .zip(share_passphrases)
+        .map(|(share, passphrase)| {
+            let info = ShareInfo {
+                split_id: hex::encode(split_id),
+                index: share.index,
+                threshold,
+                shares: count,
+            };
+            Keystore::seal_share(&share.data, info, passphrase, params)
+        })
+        .collect()
+}
+
+fn share_info(keystore: &Keystore) -> Result<&ShareInfo, Error> {
+    keystore
+        .share()
+        .ok_or_else(|| Error::Shamir("keystore doesn't hold a share".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Keep tests fast, these params are far too weak for real use
+    const TEST_PARAMS: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    const PHRASE: &str =
+        "legal winner thank year wave sausage worth useful legal winner thank yellow";
+
+    fn passphrases() -> Vec<Zeroizing<String>> {
+        ["alice", "bob", "carol"]
+            .map(|passphrase| Zeroizing::new(passphrase.to_string()))
+            .to_vec()
+    }
+
+    #[test]
+    fn test_split_and_combine() {
+        let mnemonic = bip39::Mnemonic::parse(PHRASE).unwrap();
+        let keystores = split_mnemonic(&mnemonic, 2, 3, &passphrases(), TEST_PARAMS).unwrap();
+        assert_eq!(keystores.len(), 3);
+        assert_eq!(keystores[2].share().unwrap().index, 3);
+
+        let shares = keystores.into_iter().zip(passphrases()).collect::<Vec<_>>();
+        assert_eq!(combine(&shares[1..]).unwrap(), mnemonic);
+        assert_eq!(combine(&shares).unwrap(), mnemonic);
+
+        // Below the threshold
+        assert!(combine(&shares[..1]).is_err());
+
+        // Wrong holder passphrase
+        let mut swapped = shares[..2].to_vec();
+        swapped[0].1 = Zeroizing::new("bob".to_string());
+        assert!(combine(&swapped).is_err());
+    }
+
+    #[test]
+    fn test_mixed_splits() {
+        let mnemonic = bip39::Mnemonic::parse(PHRASE).unwrap();
+        let first = split_mnemonic(&mnemonic, 2, 3, &passphrases(), TEST_PARAMS).unwrap();
+        let second = split_mnemonic(&mnemonic, 2, 3, &passphrases(), TEST_PARAMS).unwrap();
+
+        let mixed = [
+            (first[0].clone(), passphrases()[0].clone()),
+            (second[1].clone(), passphrases()[1].clone()),
+        ];
+        assert!(combine(&mixed).is_err());
+
+        let plain = Keystore::seal(PHRASE.as_bytes(), "alice", TEST_PARAMS).unwrap();
+        assert!(combine(&[(plain, passphrases()[0].clone())]).is_err());
+    }
+}
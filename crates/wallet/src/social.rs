@@ -0,0 +1,32 @@
+//! The HKDF construction used by wallets of platforms other than twitter,
+//! from [`DerivationScheme::V3`](crate::scheme::DerivationScheme::V3).
+//!
+//! Twitter IDs keep their BIP44 paths. Every other platform is derived under
+//! its own HKDF info label, so the same number on two platforms never
+//! derives the same wallet.
+
+use hkdf::Hkdf;
+use x_link_types::social::Platform;
+use zeroize::Zeroizing;
+
+/// HKDF salt, distinct from the handle salt so the two never share a PRK.
+const SALT: &[u8] = b"x-link/social/salt/v3";
+/// Prefix of the HKDF info, followed by `{platform}:` and the big endian ID.
+const INFO: &[u8] = b"x-link/social/ed25519/v3:";
+
+/// HKDF-SHA512 over the master seed, expanded into an ed25519 seed for an
+/// ID on `platform`.
+pub(crate) fn seed_for_social(
+    secret: &[u8; 64],
+    platform: Platform,
+    id: u64,
+) -> Zeroizing<[u8; 32]> {
+    let mut seed = Zeroizing::new([0u8; 32]);
+    Hkdf::<sha2::Sha512>::new(Some(SALT), secret)
+        .expand_multi_info(
+            &[INFO, platform.name().as_bytes(), b":", &id.to_be_bytes()],
+            seed.as_mut_slice(),
+        )
+        .expect("32 bytes is a valid HKDF-SHA512 output length");
+    seed
+}